@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use nom::IResult;
+use nom::branch::alt;
+use nom::bytes::complete::{is_not, tag};
+use nom::character::complete::{alphanumeric1, char, space0, u8};
+use nom::combinator::{cut, eof, map, opt, recognize};
+use nom::sequence::{delimited, preceded};
+
+use crate::error::{Error, Result};
+use crate::parse::{error_at, parse_lines};
+
+/// How an answer compares to the one recorded for the same input.
+#[derive(Debug, PartialEq)]
+pub enum Verdict {
+    Pass,
+    Fail { expected: String },
+    Unknown,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::Pass => write!(f, "pass"),
+            Verdict::Fail { expected } => write!(f, "FAIL, expected {}", expected),
+            Verdict::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+enum Line<'a> {
+    Blank,
+    Day(u8),
+    // The part, its answer and where the line starts, for reporting answers outside a day
+    Answer(u8, String, &'a str),
+}
+
+// [day3]
+fn parse_day(i: &str) -> IResult<&str, Line<'_>> {
+    map(delimited(tag("[day"), u8, char(']')), Line::Day)(i)
+}
+
+// part1 = 550064
+// part2 = "some text"
+fn parse_answer(start: &str) -> IResult<&str, Line<'_>> {
+    let (i, part) = preceded(tag("part"), u8)(start)?;
+    // Once the key has been read a bad value is an error rather than a different kind of line
+    let (i, value) = cut(preceded(
+        delimited(space0, char('='), space0),
+        alt((
+            delimited(char('"'), recognize(opt(is_not("\""))), char('"')),
+            recognize(preceded(opt(char('-')), alphanumeric1)),
+        ))))(i)?;
+
+    Ok((i, Line::Answer(part, value.to_string(), start)))
+}
+
+fn parse_line(i: &str) -> IResult<&str, Line<'_>> {
+    let (i, _) = space0(i)?;
+    let (i, line) = alt((
+        parse_day,
+        parse_answer,
+        map(preceded(char('#'), opt(is_not("\n"))), |_| Line::Blank),
+        map(eof, |_| Line::Blank),
+    ))(i)?;
+    let (i, _) = space0(i)?;
+    let (i, _) = opt(preceded(char('#'), opt(is_not("\n"))))(i)?;
+
+    Ok((i, line))
+}
+
+/// The answers recorded for a set of inputs, read from a small TOML file with a
+/// `[dayN]` table per day and a `partN` key per part.
+#[derive(Debug, Default, PartialEq)]
+pub struct ExpectedAnswers {
+    answers: HashMap<(u8, u8), String>,
+}
+
+impl ExpectedAnswers {
+    pub fn parse(input: &str) -> Result<ExpectedAnswers> {
+        // The answers file isn't any one day's input, so drop the day from parse errors
+        let answers_error = |e| match e {
+            Error::Parse { line, column, text, .. } => Error::Answers { line: line, column: column, text: text },
+            e => e,
+        };
+
+        let lines = parse_lines(0, input, parse_line).map_err(answers_error)?;
+
+        let mut answers = HashMap::new();
+        let mut day = None;
+
+        for line in lines {
+            match line {
+                Line::Blank => (),
+                Line::Day(d) => day = Some(d),
+                Line::Answer(part, value, start) => {
+                    let day = day.ok_or_else(|| answers_error(error_at(0, input, start)))?;
+
+                    answers.insert((day, part), value);
+                },
+            }
+        }
+
+        Ok(ExpectedAnswers { answers: answers })
+    }
+
+    /// Reads the answers at `path`, treating a missing file as having no answers.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Result<ExpectedAnswers>> {
+        match fs::read_to_string(path) {
+            Ok(s) => Ok(ExpectedAnswers::parse(&s)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Ok(ExpectedAnswers::default())),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn expected(&self, day: u8, part: u8) -> Option<&str> {
+        self.answers.get(&(day, part)).map(|s| s.as_str())
+    }
+
+    pub fn check(&self, day: u8, part: u8, answer: &str) -> Verdict {
+        match self.expected(day, part) {
+            Some(expected) if expected == answer => Verdict::Pass,
+            Some(expected) => Verdict::Fail { expected: expected.to_string() },
+            None => Verdict::Unknown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+# Answers for the inputs in this directory
+
+[day1]
+part1 = 142
+part2 = \"281\"  # quoted values are fine too
+
+[day5]
+part1 = 35
+";
+
+    #[test]
+    fn test_check() {
+        let answers = ExpectedAnswers::parse(EXAMPLE).expect("Unable to parse answers");
+
+        assert_eq!(answers.expected(1, 2), Some("281"));
+        assert_eq!(answers.check(1, 1, "142"), Verdict::Pass);
+        assert_eq!(answers.check(5, 1, "36"), Verdict::Fail { expected: "35".to_string() });
+        assert_eq!(answers.check(5, 2, "46"), Verdict::Unknown);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            ExpectedAnswers::parse("[day1]\npart1 == 3\n"),
+            Err(Error::Answers { line: 2, column: 8, text: "part1 == 3".to_string() }));
+        assert_eq!(
+            ExpectedAnswers::parse("\n  part1 = 3\n"),
+            Err(Error::Answers { line: 2, column: 3, text: "  part1 = 3".to_string() }));
+    }
+}
@@ -0,0 +1,127 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::error::Result;
+use crate::solution::{DynSolution, Timings};
+
+/// Summary of the time one phase took over repeated runs.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+}
+
+impl Stats {
+    /// Summarises `samples`, which must not be empty.
+    pub fn from_samples(samples: &[Duration]) -> Stats {
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+
+        let middle = sorted.len() / 2;
+        let median = if sorted.len().is_multiple_of(2) {
+            (sorted[middle - 1] + sorted[middle]) / 2
+        } else {
+            sorted[middle]
+        };
+
+        Stats {
+            min: sorted[0],
+            median: median,
+            mean: sorted.iter().sum::<Duration>() / sorted.len() as u32,
+        }
+    }
+}
+
+/// The timings for each phase of a day.
+#[derive(Debug, PartialEq)]
+pub struct DayBench {
+    pub day: u8,
+    pub parse: Stats,
+    pub part1: Stats,
+    pub part2: Stats,
+}
+
+impl DayBench {
+    fn phases(&self) -> [(&'static str, &Stats); 3] {
+        [("parse", &self.parse), ("part1", &self.part1), ("part2", &self.part2)]
+    }
+}
+
+/// Times `runs` complete runs of `solution` on `input`, stopping at the first error.
+pub fn bench_day(solution: &dyn DynSolution, input: &str, runs: usize) -> Result<DayBench> {
+    let timings = (0..runs).map(|_| solution.time(input)).collect::<Result<Vec<Timings>>>()?;
+    let stats = |phase: fn(&Timings) -> Duration|
+        Stats::from_samples(&timings.iter().map(phase).collect::<Vec<_>>());
+
+    Ok(DayBench {
+        day: solution.day(),
+        parse: stats(|t| t.parse),
+        part1: stats(|t| t.part1),
+        part2: stats(|t| t.part2),
+    })
+}
+
+/// A table with a row per phase of each day.
+pub fn format_table(benches: &[DayBench]) -> String {
+    let mut s = format!("{:<5} {:<6} {:>12} {:>12} {:>12}\n", "Day", "Phase", "min", "median", "mean");
+
+    for bench in benches {
+        for (phase, stats) in bench.phases() {
+            writeln!(s, "{:<5} {:<6} {:>12.3?} {:>12.3?} {:>12.3?}", bench.day, phase, stats.min, stats.median, stats.mean)
+                .expect("Writing to a String can't fail");
+        }
+    }
+
+    s
+}
+
+/// The same results as JSON, with every time in nanoseconds so runs from different
+/// commits can be diffed.
+pub fn format_json(runs: usize, benches: &[DayBench]) -> String {
+    let days = benches.iter()
+        .map(|bench| {
+            let phases = bench.phases().iter()
+                .map(|(phase, stats)| format!(
+                    "\"{}\": {{\"min_ns\": {}, \"median_ns\": {}, \"mean_ns\": {}}}",
+                    phase, stats.min.as_nanos(), stats.median.as_nanos(), stats.mean.as_nanos()))
+                .collect::<Vec<_>>();
+
+            format!("    {{\"day\": {}, {}}}", bench.day, phases.join(", "))
+        })
+        .collect::<Vec<_>>();
+
+    format!("{{\n  \"runs\": {},\n  \"days\": [\n{}\n  ]\n}}", runs, days.join(",\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(samples: &[u64]) -> Vec<Duration> {
+        samples.iter().map(|&m| Duration::from_millis(m)).collect()
+    }
+
+    #[test]
+    fn test_stats() {
+        assert_eq!(
+            Stats::from_samples(&ms(&[5, 1, 3])),
+            Stats { min: Duration::from_millis(1), median: Duration::from_millis(3), mean: Duration::from_millis(3) });
+        assert_eq!(
+            Stats::from_samples(&ms(&[4, 1, 2, 9])),
+            Stats { min: Duration::from_millis(1), median: Duration::from_millis(3), mean: Duration::from_millis(4) });
+    }
+
+    #[test]
+    fn test_format_json() {
+        let stats = Stats::from_samples(&ms(&[2]));
+        let bench = DayBench { day: 6, parse: stats, part1: stats, part2: stats };
+
+        assert_eq!(
+            format_json(1, &[bench]),
+            "{\n  \"runs\": 1,\n  \"days\": [\n    {\"day\": 6, \
+\"parse\": {\"min_ns\": 2000000, \"median_ns\": 2000000, \"mean_ns\": 2000000}, \
+\"part1\": {\"min_ns\": 2000000, \"median_ns\": 2000000, \"mean_ns\": 2000000}, \
+\"part2\": {\"min_ns\": 2000000, \"median_ns\": 2000000, \"mean_ns\": 2000000}}\n  ]\n}");
+    }
+}
@@ -0,0 +1,253 @@
+use std::path::PathBuf;
+
+use crate::input::InputSource;
+
+pub const USAGE: &str = "\
+Usage: advent2023 [COMMAND]
+
+Commands:
+    run --day <N> [--part <1|2>]    Run a single day, optionally only one part
+    run --all [--part <1|2>]        Run every available day
+    bench [--day <N>] [--json]      Time parsing and each part of every day, or
+                                    only the given days
+    list                            List the available days
+    help                            Show this message
+
+Run options:
+    --input <PATH>                  Read the day's input from PATH, or stdin for `-`
+    --data-dir <DIR>                Read inputs from DIR/day<N>.txt (default: data,
+                                    or $ADVENT2023_DATA_DIR when set)
+    --answers <PATH>                Check answers against PATH rather than
+                                    DIR/answers.toml
+
+Bench options:
+    --runs <N>                      Time N runs of each day (default: 10)
+    --data-dir <DIR>                As for run
+    --json                          Print the timings as JSON, in nanoseconds
+
+With no command every available day is run.";
+
+/// Which parts of a day's puzzle should be solved.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Parts {
+    pub one: bool,
+    pub two: bool,
+}
+
+impl Parts {
+    pub const BOTH: Parts = Parts { one: true, two: true };
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Run {
+        days: Option<Vec<u8>>,
+        parts: Parts,
+        input: InputSource,
+        data_dir: Option<PathBuf>,
+        answers: Option<PathBuf>,
+    },
+    Bench {
+        days: Option<Vec<u8>>,
+        runs: usize,
+        data_dir: Option<PathBuf>,
+        json: bool,
+    },
+    List,
+    Help,
+}
+
+pub const DEFAULT_BENCH_RUNS: usize = 10;
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or(format!("{} requires a value", flag))?;
+
+    value.parse::<T>().map_err(|_| format!("invalid value '{}' for {}", value, flag))
+}
+
+fn parse_run<I: Iterator<Item = String>>(mut args: I) -> Result<Command, String> {
+    let mut days = None;
+    let mut all = false;
+    let mut parts = Parts::BOTH;
+    let mut input = InputSource::DataDir;
+    let mut data_dir = None;
+    let mut answers = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--day" | "-d" => {
+                let day = parse_number::<u8>(&arg, args.next())?;
+                days.get_or_insert_with(Vec::new).push(day);
+            },
+            "--part" | "-p" => {
+                parts = match parse_number::<u8>(&arg, args.next())? {
+                    1 => Parts { one: true, two: false },
+                    2 => Parts { one: false, two: true },
+                    n => return Err(format!("there is no part {}", n)),
+                };
+            },
+            "--input" | "-i" => {
+                let arg = args.next().ok_or("--input requires a value")?;
+                input = InputSource::from_arg(&arg);
+            },
+            "--data-dir" => {
+                let arg = args.next().ok_or("--data-dir requires a value")?;
+                data_dir = Some(PathBuf::from(arg));
+            },
+            "--answers" => {
+                let arg = args.next().ok_or("--answers requires a value")?;
+                answers = Some(PathBuf::from(arg));
+            },
+            "--all" | "-a" => all = true,
+            _ => return Err(format!("unexpected argument '{}'", arg)),
+        }
+    }
+
+    if input != InputSource::DataDir && days.as_ref().is_none_or(|d| d.len() != 1) {
+        return Err("--input can only be used with a single --day".to_string());
+    }
+
+    match (all, days) {
+        (true, Some(_)) => Err("--all cannot be combined with --day".to_string()),
+        (false, None) => Err("run needs either --day <N> or --all".to_string()),
+        (_, days) => Ok(Command::Run {
+            days: days,
+            parts: parts,
+            input: input,
+            data_dir: data_dir,
+            answers: answers,
+        }),
+    }
+}
+
+fn parse_bench<I: Iterator<Item = String>>(mut args: I) -> Result<Command, String> {
+    let mut days = None;
+    let mut runs = DEFAULT_BENCH_RUNS;
+    let mut data_dir = None;
+    let mut json = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--day" | "-d" => {
+                let day = parse_number::<u8>(&arg, args.next())?;
+                days.get_or_insert_with(Vec::new).push(day);
+            },
+            "--runs" | "-n" => {
+                runs = parse_number::<usize>(&arg, args.next())?;
+
+                if runs == 0 {
+                    return Err("--runs must be at least 1".to_string());
+                }
+            },
+            "--data-dir" => {
+                let arg = args.next().ok_or("--data-dir requires a value")?;
+                data_dir = Some(PathBuf::from(arg));
+            },
+            "--json" => json = true,
+            _ => return Err(format!("unexpected argument '{}'", arg)),
+        }
+    }
+
+    Ok(Command::Bench { days: days, runs: runs, data_dir: data_dir, json: json })
+}
+
+impl Command {
+    /// What running the binary without a command does.
+    fn run_all() -> Command {
+        Command::Run { days: None, parts: Parts::BOTH, input: InputSource::DataDir, data_dir: None, answers: None }
+    }
+}
+
+/// Parses the arguments following the program name.
+pub fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Command, String> {
+    let command = match args.next() {
+        Some(command) => command,
+        None => return Ok(Command::run_all()),
+    };
+
+    let command = match command.as_str() {
+        "run" => parse_run(&mut args)?,
+        "bench" => parse_bench(&mut args)?,
+        "list" => Command::List,
+        "help" | "--help" | "-h" => Command::Help,
+        _ => return Err(format!("unknown command '{}'", command)),
+    };
+
+    if let Command::List | Command::Help = command {
+        if let Some(arg) = args.next() {
+            return Err(format!("unexpected argument '{}'", arg));
+        }
+    }
+
+    Ok(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        parse_args(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse(&[]), Ok(Command::run_all()));
+        assert_eq!(parse(&["run", "--all"]), Ok(Command::run_all()));
+        assert_eq!(
+            parse(&["run", "--day", "5", "--part", "2"]),
+            Ok(Command::Run {
+                days: Some(vec!(5)),
+                parts: Parts { one: false, two: true },
+                input: InputSource::DataDir,
+                data_dir: None,
+                answers: None,
+            }));
+        assert_eq!(parse(&["list"]), Ok(Command::List));
+
+        assert!(parse(&["run"]).is_err());
+        assert!(parse(&["run", "--all", "--day", "1"]).is_err());
+        assert!(parse(&["run", "--day", "x"]).is_err());
+        assert!(parse(&["run", "--day", "1", "--part", "3"]).is_err());
+        assert!(parse(&["frobnicate"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_input_args() {
+        assert_eq!(
+            parse(&["run", "--day", "2", "--input", "-"]),
+            Ok(Command::Run {
+                days: Some(vec!(2)),
+                parts: Parts::BOTH,
+                input: InputSource::Stdin,
+                data_dir: None,
+                answers: None,
+            }));
+        assert_eq!(
+            parse(&["run", "--all", "--data-dir", "inputs/alice"]),
+            Ok(Command::Run {
+                days: None,
+                parts: Parts::BOTH,
+                input: InputSource::DataDir,
+                data_dir: Some(PathBuf::from("inputs/alice")),
+                answers: None,
+            }));
+
+        assert!(parse(&["run", "--all", "--input", "day1.txt"]).is_err());
+        assert!(parse(&["run", "--day", "1", "--day", "2", "--input", "day1.txt"]).is_err());
+        assert!(parse(&["run", "--day", "1", "--input"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_bench_args() {
+        assert_eq!(
+            parse(&["bench"]),
+            Ok(Command::Bench { days: None, runs: DEFAULT_BENCH_RUNS, data_dir: None, json: false }));
+        assert_eq!(
+            parse(&["bench", "--day", "5", "--day", "6", "--runs", "3", "--json"]),
+            Ok(Command::Bench { days: Some(vec!(5, 6)), runs: 3, data_dir: None, json: true }));
+
+        assert!(parse(&["bench", "--runs", "0"]).is_err());
+        assert!(parse(&["bench", "--part", "1"]).is_err());
+    }
+}
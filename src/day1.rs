@@ -1,73 +1,257 @@
-use std::fs::File;
-use std::io::Read;
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::Path;
 
-pub fn run_day1() {
-    println!("Day 1!");
+use nom::IResult;
+use nom::bytes::complete::take_till1;
+use nom::character::complete::{char, satisfy, space0};
+use nom::combinator::map;
+use nom::sequence::{delimited, separated_pair};
 
-    let mut f = File::open("data/day1.txt").expect("File not found");
-    let mut s = String::new();
-    f.read_to_string(&mut s).expect("Unable to load file");
+use crate::error::{Error, Result};
+use crate::matcher::Matcher;
+use crate::parse::parse_lines;
+use crate::solution::Solution;
 
-    let lines = s.lines().filter(|s| !s.is_empty()).collect::<Vec<_>>();
+const ENGLISH_DIGITS: [&str; 9] = ["one", "two", "three", "four", "five", "six", "seven", "eight", "nine"];
 
-    println!("Loaded {} lines", lines.len());
+/// The tokens that count as digits in a calibration line, and the digit each means.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vocabulary {
+    tokens: Vec<(String, u32)>,
+}
 
-    let numeric_lines = lines.iter().map(|l| {
-        l.chars().filter(|c| c.is_numeric()).collect::<String>()
-    }).collect::<Vec<_>>();
+// seven = 7
+fn parse_token(i: &str) -> IResult<&str, (String, u32)> {
+    separated_pair(
+        map(take_till1(|c: char| c.is_whitespace() || c == '='), |t: &str| t.to_string()),
+        delimited(space0, char('='), space0),
+        map(satisfy(|c| c.is_ascii_digit()), |c| c.to_digit(10).expect("Is an ASCII digit")))(i)
+}
 
-    let total = numeric_lines.iter().map(|l| {
-        let mut cs = l.chars().collect::<Vec<_>>();
-        let f = cs[0];
-        cs.reverse();
-        let e = cs[0];
+impl Vocabulary {
+    /// Only the numerals `0` to `9`.
+    pub fn digits() -> Vocabulary {
+        Vocabulary { tokens: (0..10).map(|d| (d.to_string(), d)).collect() }
+    }
 
-        return f.to_digit(10).expect("First is not a digit") * 10 + e.to_digit(10).expect("Last is not a digit");
-    }).sum::<u32>();
+    /// The numerals and the English words `one` to `nine`.
+    pub fn english() -> Vocabulary {
+        ENGLISH_DIGITS.iter()
+            .zip(1..)
+            .fold(Vocabulary::digits(), |vocabulary, (word, d)| vocabulary.push(word, d))
+    }
 
-    println!("Part 1 total: {}", total);
+    /// Adds `token` as another way of writing `digit`, replacing any earlier meaning.
+    pub fn with(self, token: &str, digit: u32) -> Result<Vocabulary> {
+        if digit >= 10 {
+            return Err(Error::NotADigit { token: token.to_string(), value: digit });
+        }
 
-    let numeric_spelled_lines = 
-        lines.iter()
-            .map(|l| 
-                convert_numbers(l.to_string()).chars().filter(|c| c.is_numeric()).collect::<String>()
-            ).collect::<Vec<_>>();
+        Ok(self.push(token, digit))
+    }
 
-    let spelled_total = numeric_spelled_lines.iter().map(|l| {
-        let mut cs = l.chars().collect::<Vec<_>>();
-        let f = cs[0];
-        cs.reverse();
-        let e = cs[0];
+    // For digits that are already known to be 0 to 9
+    fn push(mut self, token: &str, digit: u32) -> Vocabulary {
+        self.tokens.push((token.to_string(), digit));
+        self
+    }
+
+    /// Reads a `token = digit` line per word, on top of the numerals.
+    pub fn parse(input: &str) -> Result<Vocabulary> {
+        let tokens = parse_lines(1, input, parse_token).map_err(|e| match e {
+            Error::Parse { line, column, text, .. } => Error::Vocabulary { line: line, column: column, text: text },
+            e => e,
+        })?;
 
-        return f.to_digit(10).expect("First is not a digit") * 10 + e.to_digit(10).expect("Last is not a digit");
-    }).sum::<u32>();
+        Ok(tokens.iter().fold(Vocabulary::digits(), |vocabulary, (token, d)| vocabulary.push(token, *d)))
+    }
 
-    println!("Part 2 total: {}", spelled_total);
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Result<Vocabulary>> {
+        Ok(Vocabulary::parse(&fs::read_to_string(path)?))
+    }
 
+    pub fn compile(&self) -> Calibrator {
+        Calibrator { matcher: Matcher::new(self.tokens.iter().map(|(t, d)| (t.as_str(), *d))) }
+    }
+}
+
+/// Reads calibration values using a compiled `Vocabulary`.
+#[derive(Debug)]
+pub struct Calibrator {
+    matcher: Matcher<u32>,
 }
 
-fn convert_numbers(s: String) -> String {
-    return s.replace("one", "o1e")
-    .replace("two", "t2o")
-    .replace("three", "t3e")
-    .replace("four", "f4r")
-    .replace("five", "f5e")
-    .replace("six", "s6x")
-    .replace("seven", "s7n")
-    .replace("eight", "e8t")
-    .replace("nine", "n9e")
-    .chars().filter(|c| c.is_numeric()).collect::<String>();
+impl Calibrator {
+    /// The first and last digits as a two digit number, or `None` for a line with no
+    /// digits. Tokens may overlap, and where two start together the longer one counts.
+    pub fn calibration_value(&self, line: &str) -> Option<u32> {
+        let mut first: Option<(usize, usize, u32)> = None;
+        let mut last: Option<(usize, usize, u32)> = None;
+
+        for m in self.matcher.find_all(line) {
+            let (start, length) = (m.span.start, m.span.len());
+
+            if first.is_none_or(|(s, l, _)| start < s || (start == s && length > l)) {
+                first = Some((start, length, m.value));
+            }
+
+            if last.is_none_or(|(s, l, _)| start > s || (start == s && length > l)) {
+                last = Some((start, length, m.value));
+            }
+        }
+
+        return Some(first?.2 * 10 + last?.2);
+    }
+}
+
+/// The calibration totals for both parts.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct Totals {
+    pub part1: u64,
+    pub part2: u64,
+}
+
+/// Totals both parts in one pass over `reader`, a line at a time, so memory use
+/// doesn't grow with the input.
+pub fn calibrate<R: BufRead>(reader: R) -> io::Result<Totals> {
+    calibrate_with(reader, &Vocabulary::digits().compile(), &Vocabulary::english().compile())
+}
+
+/// As `calibrate`, reading each part's digits with its own calibrator. Lines
+/// without any digits have no calibration value, so add nothing.
+pub fn calibrate_with<R: BufRead>(mut reader: R, part1: &Calibrator, part2: &Calibrator) -> io::Result<Totals> {
+    let mut totals = Totals::default();
+    let mut line = String::new();
+
+    while reader.read_line(&mut line)? != 0 {
+        totals.part1 += part1.calibration_value(&line).map_or(0, u64::from);
+        totals.part2 += part2.calibration_value(&line).map_or(0, u64::from);
+        line.clear();
+    }
+
+    Ok(totals)
+}
+
+/// Sums the calibration values of `lines`, reading digits with `calibrator`.
+fn total(calibrator: &Calibrator, lines: &[String]) -> u64 {
+    lines.iter().map(|line| calibrator.calibration_value(line).map_or(0, u64::from)).sum()
+}
+
+pub struct Day1;
+
+impl Solution for Day1 {
+    type Input = Vec<String>;
+    type Part1 = u64;
+    type Part2 = u64;
+
+    fn day(&self) -> u8 {
+        1
+    }
+
+    fn parse(&self, input: &str) -> Result<Vec<String>> {
+        Ok(input.lines().map(String::from).collect())
+    }
+
+    fn part1(&self, lines: &Vec<String>) -> Result<u64> {
+        Ok(total(&Vocabulary::digits().compile(), lines))
+    }
+
+    fn part2(&self, lines: &Vec<String>) -> Result<u64> {
+        Ok(total(&Vocabulary::english().compile(), lines))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn english_digits(line: &str) -> Vec<u32> {
+        Vocabulary::english().compile().matcher.find_all(line).map(|m| m.value).collect()
+    }
+
+    #[test]
+    fn test_spelled_digits() {
+        assert_eq!(english_digits("5four1bvggfs62nineone"), vec!(5, 4, 1, 6, 2, 9, 1));
+        assert_eq!(english_digits("two1nine"), vec!(2, 1, 9));
+        assert_eq!(english_digits("xtwone3four"), vec!(2, 1, 3, 4));
+        assert_eq!(english_digits("zoneight234"), vec!(1, 8, 2, 3, 4));
+        assert_eq!(english_digits("eightwothreeightwo"), vec!(8, 2, 3, 8, 2));
+        assert_eq!(english_digits("oneight"), vec!(1, 8));
+    }
+
+    #[test]
+    fn test_calibration_value() {
+        let digits = Vocabulary::digits().compile();
+        let english = Vocabulary::english().compile();
+
+        assert_eq!(digits.calibration_value("treb7uchet"), Some(77));
+        assert_eq!(digits.calibration_value("eightwothree"), None);
+        assert_eq!(english.calibration_value("eightwothree"), Some(83));
+        assert_eq!(english.calibration_value("abcdef"), None);
+        assert_eq!(english.calibration_value(""), None);
+        assert_eq!(english.calibration_value("×3ñtwoñ"), Some(32));
+    }
+
+    #[test]
+    fn test_custom_vocabulary() {
+        let vocabulary = Vocabulary::parse("zero = 0\ndrei=3\nzwölf = 1\nzw = 2\n").expect("Unable to parse vocabulary");
+        let calibrator = vocabulary.compile();
+
+        assert_eq!(calibrator.calibration_value("zerodrei"), Some(3));
+        assert_eq!(calibrator.calibration_value("xdreizero"), Some(30));
+        // zwölf and zw start together, and the longer one wins
+        assert_eq!(calibrator.calibration_value("zwölf"), Some(11));
+        assert_eq!(calibrator.calibration_value("zwei"), Some(22));
+        assert_eq!(calibrator.calibration_value("one"), None);
+
+        let calibrator = Vocabulary::digits().with("un", 1)
+            .and_then(|v| v.with("deux", 2))
+            .and_then(|v| v.with("un", 7))
+            .expect("Tokens are all digits")
+            .compile();
+
+        assert_eq!(calibrator.calibration_value("deuxun"), Some(27));
+        assert_eq!(
+            Vocabulary::digits().with("dix", 10),
+            Err(Error::NotADigit { token: "dix".to_string(), value: 10 }));
+
+        assert_eq!(
+            Vocabulary::parse("zero = 0\nten = 10\n"),
+            Err(Error::Vocabulary { line: 2, column: 8, text: "ten = 10".to_string() }));
+    }
+
+    // Produces the same line forever, so a generated input is never held in memory
+    struct RepeatedLine {
+        line: &'static [u8],
+        pos: usize,
+    }
+
+    impl io::Read for RepeatedLine {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = buf.len().min(self.line.len() - self.pos);
+
+            buf[..n].copy_from_slice(&self.line[self.pos..self.pos + n]);
+            self.pos = (self.pos + n) % self.line.len();
+            Ok(n)
+        }
+    }
+
     #[test]
-    fn test_convert_numbers() {
-        assert_eq!(convert_numbers("5four1bvggfs62nineone".to_string()), "5416291");
-        assert_eq!(convert_numbers("two1nine".to_string()), "219");
-        assert_eq!(convert_numbers("xtwone3four".to_string()), "2134");
-        assert_eq!(convert_numbers("zoneight234".to_string()), "18234");
+    fn test_calibrate() {
+        let example = "1abc2\r\npqr3stu8vwx\nxtwone3four\n\nnodigits\n7pqrstsixteen";
+
+        assert_eq!(
+            calibrate(example.as_bytes()).expect("Unable to read"),
+            Totals { part1: 12 + 38 + 33 + 77, part2: 12 + 38 + 24 + 76 });
+
+        let lines = 20_000;
+        let generated = io::Read::take(RepeatedLine { line: b"8five\n", pos: 0 }, 6 * lines);
+
+        assert_eq!(
+            calibrate(io::BufReader::new(generated)).expect("Unable to read"),
+            Totals { part1: 88 * lines, part2: 85 * lines });
+
+        assert_eq!(calibrate(&b"12\n\xff\n"[..]).map_err(|e| e.kind()), Err(io::ErrorKind::InvalidData));
     }
-}
\ No newline at end of file
+}
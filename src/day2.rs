@@ -1,62 +1,191 @@
-use std::fs::File;
-use std::io::Read;
+use std::collections::{BTreeMap, BTreeSet};
 
 use nom::IResult;
-use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::character::complete::u32;
-use nom::combinator::value;
+use nom::character::complete::{alpha1, u32};
 use nom::multi::separated_list1;
+use nom::sequence::separated_pair;
 
-#[derive(Debug,PartialEq)]
-struct Round {
-    red: u32,
-    green: u32,
-    blue: u32,
+use crate::error::{Error, Result};
+use crate::parse::parse_lines;
+use crate::solution::Solution;
+
+/// The bag part 1 asks about.
+const PART_1_BAG: [(&str, u32); 3] = [("red", 12), ("green", 13), ("blue", 14)];
+
+/// Numbers of cubes by colour, either shown in a round or held in a bag.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Cubes {
+    counts: BTreeMap<String, u32>,
+}
+
+impl Cubes {
+    pub fn new<'a, I: IntoIterator<Item = (&'a str, u32)>>(counts: I) -> Cubes {
+        let mut cubes = Cubes::default();
+
+        for (colour, count) in counts {
+            *cubes.counts.entry(colour.to_string()).or_insert(0) += count;
+        }
+
+        cubes
+    }
+
+    /// How many cubes of `colour` there are, which is none for colours not listed.
+    pub fn get(&self, colour: &str) -> u32 {
+        self.counts.get(colour).copied().unwrap_or(0)
+    }
+
+    pub fn contains(&self, colour: &str) -> bool {
+        self.counts.contains_key(colour)
+    }
+
+    pub fn colours(&self) -> impl Iterator<Item = &str> {
+        self.counts.keys().map(|c| c.as_str())
+    }
 }
 
 #[derive(Debug)]
-struct Game {
+pub struct Game {
     id: u32,
-    rounds: Vec<Round>,
+    rounds: Vec<Cubes>,
 }
 
-fn parse_color(i: &str) -> IResult<&str, (&str, u32)> {
-    let (i, count) = u32(i)?;
-    let (i, _) = tag(" ")(i)?;
-    let (i, color) = alt((
-        value("red", tag("red")),
-        value("green", tag("green")),
-        value("blue", tag("blue")),
-    ))(i)?;
+impl Game {
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Whether every round could have been drawn from `bag`. Colours the bag
+    /// doesn't list at all are an error rather than a count of zero, as they're
+    /// more likely a mistake in the bag.
+    pub fn is_possible(&self, bag: &Cubes) -> Result<bool> {
+        let mut possible = true;
+
+        for round in self.rounds.iter() {
+            for colour in round.colours() {
+                if !bag.contains(colour) {
+                    return Err(Error::UnknownColour { day: 2, game: self.id, colour: colour.to_string() });
+                }
+
+                possible &= round.get(colour) <= bag.get(colour);
+            }
+        }
 
-    Ok((i, (color, count)))
+        Ok(possible)
+    }
+
+    /// The fewest cubes of each colour the game could have been played with.
+    pub fn minimum_bag(&self) -> Cubes {
+        let mut bag = Cubes::default();
+
+        for round in self.rounds.iter() {
+            for (colour, &count) in round.counts.iter() {
+                let most = bag.counts.entry(colour.clone()).or_insert(0);
+                *most = (*most).max(count);
+            }
+        }
+
+        bag
+    }
+
+    pub fn colours(&self) -> impl Iterator<Item = &str> {
+        self.rounds.iter().flat_map(|r| r.colours())
+    }
 }
 
-fn parse_round(i: &str) -> IResult<&str, Round> {
-    let (i, colors) = separated_list1(
-        tag(", "),
-        parse_color
-    )(i)?;
+/// The games that could have been played with `bag`.
+pub fn possible_games<'a>(games: &'a [Game], bag: &Cubes) -> Result<Vec<&'a Game>> {
+    let mut possible = Vec::new();
 
-    let mut round = Round { red: 0, green: 0, blue: 0 };
+    for game in games {
+        if game.is_possible(bag)? {
+            possible.push(game);
+        }
+    }
+
+    Ok(possible)
+}
+
+/// The smallest bag every game could have been played with.
+pub fn smallest_bag(games: &[Game]) -> Cubes {
+    smallest_bag_for(games, games.len()).expect("There are enough games")
+}
 
-    match colors.iter().find(|&x| x.0 == "red") {
-        Some((_, c)) => round.red = *c,
-        None => ()
+/// A bag with the fewest cubes in total that makes at least `n` of the games
+/// possible, or `None` when there aren't `n` games. It lists every colour any game
+/// shows, so it can be checked against all of them.
+///
+/// This searches every useful count for each colour but the last, so it's only
+/// quick for the handful of colours games use.
+pub fn smallest_bag_for(games: &[Game], n: usize) -> Option<Cubes> {
+    if n > games.len() {
+        return None;
     }
 
-    match colors.iter().find(|&x| x.0 == "green") {
-        Some((_, c)) => round.green = *c,
-        None => ()
+    let colours = games.iter().flat_map(|g| g.colours()).collect::<BTreeSet<_>>().into_iter().collect::<Vec<_>>();
+    let needs = games.iter().map(|g| g.minimum_bag()).collect::<Vec<_>>();
+    let mut best = None;
+
+    search_bags(&colours, n, needs.iter().collect(), &mut Vec::new(), &mut best);
+
+    let (_, counts) = best.expect("Taking the largest needs makes every game possible");
+
+    Some(Cubes::new(colours.into_iter().zip(counts)))
+}
+
+// Picks a count for the next colour, keeping the games that still fit, and records
+// the smallest complete bag as (total, counts)
+fn search_bags(colours: &[&str], n: usize, fitting: Vec<&Cubes>, counts: &mut Vec<u32>, best: &mut Option<(u32, Vec<u32>)>) {
+    let total = counts.iter().sum::<u32>();
+
+    let Some((&colour, rest)) = colours.split_first() else {
+        if best.as_ref().is_none_or(|(t, _)| total < *t) {
+            *best = Some((total, counts.clone()));
+        }
+        return;
+    };
+
+    let mut options = fitting.iter().map(|c| c.get(colour)).collect::<Vec<_>>();
+    options.sort();
+    options.dedup();
+
+    if n == 0 {
+        options = vec!(0);
+    } else if rest.is_empty() {
+        // With one colour left the n-th smallest need is the only count worth trying
+        let mut needs = fitting.iter().map(|c| c.get(colour)).collect::<Vec<_>>();
+        needs.sort();
+        options = vec!(needs[n - 1]);
     }
 
-    match colors.iter().find(|&x| x.0 == "blue") {
-        Some((_, c)) => round.blue = *c,
-        None => ()
+    for count in options {
+        // Counts are tried smallest first, so nothing later can beat the best
+        if best.as_ref().is_some_and(|(t, _)| total + count >= *t) {
+            break;
+        }
+
+        let fits = fitting.iter().copied().filter(|c| c.get(colour) <= count).collect::<Vec<_>>();
+
+        if fits.len() >= n {
+            counts.push(count);
+            search_bags(rest, n, fits, counts, best);
+            counts.pop();
+        }
     }
+}
 
-    Ok((i, round))
+// 3 blue
+fn parse_cubes(i: &str) -> IResult<&str, (&str, u32)> {
+    let (i, (count, colour)) = separated_pair(u32, tag(" "), alpha1)(i)?;
+
+    Ok((i, (colour, count)))
+}
+
+// 2 red, 2 green, 4 blue
+fn parse_round(i: &str) -> IResult<&str, Cubes> {
+    let (i, cubes) = separated_list1(tag(", "), parse_cubes)(i)?;
+
+    Ok((i, Cubes::new(cubes)))
 }
 
 // Game 17: 5 blue; 1 blue; 2 red, 2 green, 4 blue; 6 blue, 4 green, 2 red
@@ -73,38 +202,114 @@ fn parse_game(i: &str) -> IResult<&str, Game> {
     Ok((i, Game { id: id, rounds: rounds}))
 }
 
-pub fn run_day2() {
-    println!("Start day 2!");
+pub struct Day2;
+
+impl Solution for Day2 {
+    type Input = Vec<Game>;
+    type Part1 = u32;
+    type Part2 = u32;
+
+    fn day(&self) -> u8 {
+        2
+    }
+
+    fn parse(&self, input: &str) -> Result<Vec<Game>> {
+        parse_lines(self.day(), input, parse_game)
+    }
+
+    fn part1(&self, games: &Vec<Game>) -> Result<u32> {
+        Ok(possible_games(games, &Cubes::new(PART_1_BAG))?.iter().map(|g| g.id()).sum::<u32>())
+    }
+
+    fn part2(&self, games: &Vec<Game>) -> Result<u32> {
+        // A game that never shows some colour from elsewhere in the input needs none
+        // of it, so its power is zero
+        let colours = games.iter().flat_map(|g| g.colours()).collect::<BTreeSet<_>>();
+
+        Ok(games.iter()
+            .map(|g| {
+                let bag = g.minimum_bag();
+                colours.iter().map(|c| bag.get(c)).product::<u32>()
+            })
+            .sum::<u32>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let mut f = File::open("data/day2.txt").expect("File not found");
-    let mut s = String::new();
-    f.read_to_string(&mut s).expect("Unable to load file");
+    const EXAMPLE: &str = "\
+Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green
+";
 
-    let lines = s.lines().filter(|s| !s.is_empty()).collect::<Vec<_>>();
+    #[test]
+    fn test_other_colours() {
+        let games = Day2.parse("Game 1: 2 purple, 1 red; 3 purple, 1 teal\nGame 2: 2 red, 1 teal\n").expect("Unable to parse games");
+        let bag = Cubes::new([("purple", 3), ("red", 1), ("teal", 1)]);
 
-    println!("Loaded {} lines", lines.len());
+        assert_eq!(games[0].is_possible(&bag), Ok(true));
+        assert_eq!(games[1].is_possible(&bag), Ok(false));
+        assert_eq!(games[0].minimum_bag(), Cubes::new([("purple", 3), ("red", 1), ("teal", 1)]));
+        // Game 2 shows no purple cubes, so only game 1 has any power
+        assert_eq!(Day2.part2(&games), Ok(3));
 
-    let games = lines.iter().map(|l| parse_game(l).expect("Unable to parse game").1).collect::<Vec<_>>();
+        assert_eq!(
+            games[0].is_possible(&Cubes::new(PART_1_BAG)),
+            Err(Error::UnknownColour { day: 2, game: 1, colour: "purple".to_string() }));
+        assert_eq!(
+            Day2.part1(&games),
+            Err(Error::UnknownColour { day: 2, game: 1, colour: "purple".to_string() }));
+    }
+
+    #[test]
+    fn test_example() {
+        let games = Day2.parse(EXAMPLE).expect("Unable to parse games");
+
+        assert_eq!(Day2.part1(&games), Ok(8));
+        assert_eq!(Day2.part2(&games), Ok(2286));
+    }
 
-    let valid_games = 
-        games.iter()
-            .filter(|g| 
-                g.rounds.iter().all(|r| 
-                    r.red <= 12 && r.green <= 13 && r.blue <= 14));
-    
-    let valid_id_total = valid_games.map(|g| g.id).sum::<u32>();
+    #[test]
+    fn test_bag_queries() {
+        let games = Day2.parse(EXAMPLE).expect("Unable to parse games");
+        let ids = |games: Vec<&Game>| games.iter().map(|g| g.id()).collect::<Vec<_>>();
 
-    println!("Valid id total: {}", valid_id_total);
+        assert_eq!(games[0].minimum_bag(), Cubes::new([("red", 4), ("green", 2), ("blue", 6)]));
+        assert_eq!(possible_games(&games, &Cubes::new(PART_1_BAG)).map(ids), Ok(vec!(1, 2, 5)));
+        assert_eq!(smallest_bag(&games), Cubes::new([("red", 20), ("green", 13), ("blue", 15)]));
+        assert_eq!(smallest_bag_for(&games, 3), Some(Cubes::new([("red", 6), ("green", 3), ("blue", 6)])));
+        assert_eq!(smallest_bag_for(&games, 1), Some(Cubes::new([("red", 1), ("green", 3), ("blue", 4)])));
+        assert_eq!(smallest_bag_for(&games, 0), Some(Cubes::new([("red", 0), ("green", 0), ("blue", 0)])));
+        assert_eq!(smallest_bag_for(&games, 6), None);
+    }
+
+    #[test]
+    fn test_smallest_bag_for_matches_every_subset() {
+        let games = Day2.parse(EXAMPLE).expect("Unable to parse games");
+        let size = |bag: &Cubes| bag.colours().map(|c| bag.get(c)).sum::<u32>();
 
-    let game_powers =
-                    games.iter()
-                        .map(|g| {
-                            let max_r = g.rounds.iter().map(|r| r.red).max().expect("");
-                            let max_g = g.rounds.iter().map(|r| r.green).max().expect("");
-                            let max_b = g.rounds.iter().map(|r| r.blue).max().expect("");
+        for n in 0..=games.len() {
+            let bag = smallest_bag_for(&games, n).expect("There are enough games");
 
-                            max_r * max_b * max_g
-                        }).sum::<u32>();
+            // The smallest bag covering each subset of n games is the largest of their needs
+            let best = (0..1u32 << games.len())
+                .filter(|subset| subset.count_ones() as usize == n)
+                .map(|subset| ["red", "green", "blue"].iter()
+                    .map(|c| (0..games.len())
+                        .filter(|i| subset & (1 << i) != 0)
+                        .map(|i| games[i].minimum_bag().get(c))
+                        .max()
+                        .unwrap_or(0))
+                    .sum::<u32>())
+                .min();
 
-    println!("Miniumum powers: {}", game_powers);
-}
\ No newline at end of file
+            assert_eq!(Some(size(&bag)), best);
+            assert!(possible_games(&games, &bag).expect("Bag has every colour").len() >= n);
+        }
+    }
+}
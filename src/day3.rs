@@ -1,166 +1,165 @@
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::Read;
+use std::ops::Range;
 
-use derivative::Derivative;
+use crate::error::Result;
+use crate::grid::Grid;
+use crate::solution::Solution;
 
-#[derive(Debug, Eq, Derivative)]
-#[derivative(PartialEq, Hash)]
-enum Cell {
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Cell {
     Empty,
     Symbol(char),
-    Digit(
-        #[derivative(PartialEq="ignore")]
-        #[derivative(Hash="ignore")]
-        u32, 
-        #[derivative(PartialEq="ignore")]
-        #[derivative(Hash="ignore")]
-        usize, 
-        u32)
+    Digit(u32),
 }
 
 fn char_to_cell(c: char) -> Cell {
     if c == '.' {
         return Cell::Empty;
-    } else if c.is_numeric() {
-        return Cell::Digit(c.to_digit(10).expect("Unable to parse number as digit"), 0, 0);
+    } else if let Some(d) = c.to_digit(10) {
+        return Cell::Digit(d);
     } else {
         return Cell::Symbol(c);
     }
 }
 
-pub fn run_day3() {
-    println!("Start day 3!");
-
-    let mut f = File::open("data/day3.txt").expect("File not found");
-    let mut s = String::new();
-    f.read_to_string(&mut s).expect("Unable to load file");
-
-    let lines = s.lines().filter(|s| !s.is_empty()).collect::<Vec<_>>();
-
-    let width = lines[0].len();
-    let height = lines.len();
-
-    let mut engine = lines.iter().map(|line| {
-        line.chars().map(char_to_cell).collect::<Vec<_>>()
-    }).collect::<Vec<_>>();
-
-    let mut number_id = 0;
+/// A number written in the schematic, spanning `columns` of `row`.
+#[derive(Debug, PartialEq)]
+pub struct PartNumber {
+    pub id: usize,
+    pub value: u32,
+    pub row: usize,
+    pub columns: Range<usize>,
+}
 
-    for y in 0..height {
-        let mut x = 0;
+#[derive(Debug)]
+pub struct Schematic {
+    cells: Grid<Cell>,
+    numbers: Vec<PartNumber>,
+    // The id of the number covering each cell, if any
+    number_ids: Grid<Option<usize>>,
+}
 
-        while x < width {
-            let c = &engine[y][x];
+impl Schematic {
+    fn new(cells: Grid<Cell>) -> Schematic {
+        let mut number_ids = cells.map(|_| None);
+
+        let numbers = cells.row_runs(|c| matches!(c, Cell::Digit(_)))
+            .into_iter()
+            .enumerate()
+            .map(|(id, (row, columns))| {
+                let value = columns.clone().fold(0, |value, x| match cells[(x, row)] {
+                    Cell::Digit(d) => value * 10 + d,
+                    Cell::Empty | Cell::Symbol(_) => value,
+                });
+
+                for x in columns.clone() {
+                    number_ids[(x, row)] = Some(id);
+                }
 
-            match c {
-                Cell::Empty => (),
-                Cell::Symbol(_) => (),
-                Cell::Digit(_, _, _) => {
-                    // Find the width and value of the number
-                    let mut w = 0;
-                    let mut num = 0;
+                PartNumber { id: id, value: value, row: row, columns: columns }
+            })
+            .collect();
 
-                    for dx in x..width {
-                        match engine[y][dx] {
-                            Cell::Empty => break,
-                            Cell::Symbol(_) => break,
-                            Cell::Digit(n, _, _) => {
-                                num *= 10;
-                                num += n;
-                                w += 1;
-                            }
-                        }
-                    }
+        Schematic { cells: cells, numbers: numbers, number_ids: number_ids }
+    }
 
-                    for dx in x..(x + w) {
-                        engine[y][dx] = Cell::Digit(num, w, number_id)
-                    }
+    /// The number covering `(x, y)`, if there is one.
+    pub fn number_at(&self, x: usize, y: usize) -> Option<&PartNumber> {
+        self.number_ids.get(x, y).copied().flatten().map(|id| &self.numbers[id])
+    }
 
-                    number_id += 1;
-                    x += w - 1;
-                }
-            }
+    /// The distinct numbers touching `(x, y)`, including diagonally.
+    pub fn adjacent_numbers(&self, x: usize, y: usize) -> Vec<&PartNumber> {
+        let mut numbers = 
+            self.cells.neighbours8(x, y)
+                .filter_map(|(nx, ny)| self.number_at(nx, ny))
+                .collect::<Vec<_>>();
 
-            x += 1;
-        }
+        numbers.sort_by_key(|n| n.id);
+        numbers.dedup_by_key(|n| n.id);
+        numbers
     }
 
-    let mut part_number_total = 0;
-
-    for y in 0..height {
-        let mut x = 0;
+    /// Whether any cell around the number holds a symbol.
+    pub fn is_part_number(&self, number: &PartNumber) -> bool {
+        number.columns.clone().any(|x| 
+            self.cells.neighbours8(x, number.row).any(|n| matches!(self.cells[n], Cell::Symbol(_))))
+    }
+}
 
-        while x < width {
-            let c = &engine[y][x];
+pub struct Day3;
 
-            match c {
-                Cell::Empty => (),
-                Cell::Symbol(_) => (),
-                Cell::Digit(val, w, _) => {
-                    // Check if there is a part adjacent to the number
-                    let mut has_part = false;
-                    let skip = if x == 0 { x } else { x - 1 };
-                    if y != 0 {
-                        has_part |= engine[y - 1].iter().skip(skip).take(w + 2).any(|c| matches!(c, Cell::Symbol(_)));
-                    }
-                    has_part |= engine[y].iter().skip(skip).take(w + 2).any(|c| matches!(c, Cell::Symbol(_)));
+impl Solution for Day3 {
+    type Input = Schematic;
+    type Part1 = u32;
+    type Part2 = u32;
 
-                    if y != height - 1 {
-                        has_part |= engine[y + 1].iter().skip(skip).take(w + 2).any(|c| matches!(c, Cell::Symbol(_)));
-                    }
+    fn day(&self) -> u8 {
+        3
+    }
 
-                    if has_part {
-                        part_number_total += val;
-                    }
+    fn parse(&self, s: &str) -> Result<Schematic> {
+        Ok(Schematic::new(Grid::parse(self.day(), s, char_to_cell)?))
+    }
 
-                    x += w - 1;
-                }
-            }
+    fn part1(&self, schematic: &Schematic) -> Result<u32> {
+        let part_number_total = 
+            schematic.numbers.iter()
+                .filter(|n| schematic.is_part_number(n))
+                .map(|n| n.value)
+                .sum::<u32>();
 
-            x += 1;
-        }
+        Ok(part_number_total)
     }
 
-    println!("Part one result: {}", part_number_total);
+    fn part2(&self, schematic: &Schematic) -> Result<u32> {
+        let ratio_total = 
+            schematic.cells.positions()
+                .filter(|&p| schematic.cells[p] == Cell::Symbol('*'))
+                .map(|(x, y)| schematic.adjacent_numbers(x, y))
+                .filter(|numbers| numbers.len() == 2)
+                .map(|numbers| numbers[0].value * numbers[1].value)
+                .sum::<u32>();
 
-    if part_number_total != 550064 {
-        panic!("Incorrect value for Day 3 Part 1");
+        Ok(ratio_total)
     }
+}
 
-    let mut ratio_total = 0;
-
-    for y in 0..height {
-        for x in 0..width {
-            let c = &engine[y][x];
-
-            match c {
-                Cell::Symbol('*') => {
-                    let mut adjacent_cells = HashSet::new();
-                    let skip = if x == 0 { x } else { x - 1 };
-
-                    if y != 0 {
-                        adjacent_cells.extend(engine[y - 1].iter().skip(skip).take(3));
-                    }
-                    adjacent_cells.extend(engine[y].iter().skip(skip).take(3));
-                    if y != height - 1 {
-                        adjacent_cells.extend(engine[y + 1].iter().skip(skip).take(3));
-                    }
-
-                    let adjacent_numbers = adjacent_cells.iter().filter(|c| matches!(c, Cell::Digit(_, _, _))).collect::<Vec<_>>();
-
-                    if adjacent_numbers.len() == 2 {
-                        if let Cell::Digit(a, _, _) = adjacent_numbers[0] {
-                            if let Cell::Digit(b, _, _) = adjacent_numbers[1] {
-                                ratio_total += a * b;
-                            }
-                        }
-                    }
-                },
-                Cell::Empty | Cell::Digit(_, _, _) | Cell::Symbol(_) => ()
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+467..114..
+...*......
+..35..633.
+......#...
+617*......
+.....+.58.
+..592.....
+......755.
+...$.*....
+.664.598..
+";
+
+    #[test]
+    fn test_gear_ratios() {
+        let schematic = Day3.parse(EXAMPLE).expect("Unable to parse schematic");
+
+        assert_eq!(Day3.part2(&schematic), Ok(467835));
     }
 
-    println!("Part two result: {}", ratio_total);
-}
\ No newline at end of file
+    #[test]
+    fn test_number_spans() {
+        let schematic = Day3.parse(EXAMPLE).expect("Unable to parse schematic");
+
+        assert_eq!(schematic.numbers.len(), 10);
+        assert_eq!(schematic.number_at(6, 2), Some(&PartNumber { id: 3, value: 633, row: 2, columns: 6..9 }));
+        assert_eq!(schematic.number_at(3, 1), None);
+        assert_eq!(
+            schematic.adjacent_numbers(3, 1).iter().map(|n| n.value).collect::<Vec<_>>(),
+            vec!(467, 35));
+        assert!(!schematic.is_part_number(schematic.number_at(5, 0).expect("114 is at (5, 0)")));
+        assert!(!schematic.is_part_number(schematic.number_at(7, 5).expect("58 is at (7, 5)")));
+        assert!(schematic.is_part_number(schematic.number_at(0, 0).expect("467 is at (0, 0)")));
+    }
+}
@@ -1,27 +1,92 @@
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::Read;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
 
 use nom::IResult;
 use nom::character::complete::multispace1;
 use nom::multi::separated_list1;
 use nom::bytes::complete::tag;
-use nom::character::complete::i32;
+use nom::character::complete::{i32, u32};
 
-struct Card {
-    winning: HashSet<i32>,
-    numbers: HashSet<i32>,
+use crate::error::{Error, Result};
+use crate::parse::parse_lines;
+use crate::solution::Solution;
+
+/// A set of card numbers. Numbers from 0 to 255 are bits in two words, so
+/// intersecting two sets is a few ANDs and popcounts, and anything else falls
+/// back to hashing.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NumberSet {
+    bits: [u128; 2],
+    others: HashSet<i32>,
+}
+
+impl NumberSet {
+    pub fn insert(&mut self, n: i32) {
+        match usize::try_from(n) {
+            Ok(n) if n < 256 => self.bits[n / 128] |= 1 << (n % 128),
+            _ => {
+                self.others.insert(n);
+            },
+        }
+    }
+
+    pub fn contains(&self, n: i32) -> bool {
+        match usize::try_from(n) {
+            Ok(n) if n < 256 => self.bits[n / 128] & (1 << (n % 128)) != 0,
+            _ => self.others.contains(&n),
+        }
+    }
+
+    /// How many numbers are in both sets.
+    pub fn intersection_count(&self, other: &NumberSet) -> usize {
+        let bits = self.bits.iter().zip(other.bits.iter()).map(|(a, b)| (a & b).count_ones() as usize).sum::<usize>();
+        let (small, large) = if self.others.len() <= other.others.len() {
+            (&self.others, &other.others)
+        } else {
+            (&other.others, &self.others)
+        };
+
+        bits + small.iter().filter(|n| large.contains(n)).count()
+    }
+}
+
+impl FromIterator<i32> for NumberSet {
+    fn from_iter<I: IntoIterator<Item = i32>>(numbers: I) -> NumberSet {
+        let mut set = NumberSet::default();
+
+        for n in numbers {
+            set.insert(n);
+        }
+
+        set
+    }
+}
+
+pub struct Card {
+    pub id: u32,
+    winning: NumberSet,
+    numbers: NumberSet,
+}
+
+impl Card {
+    pub fn new(id: u32, winning: &[i32], numbers: &[i32]) -> Card {
+        Card {
+            id: id,
+            winning: winning.iter().copied().collect(),
+            numbers: numbers.iter().copied().collect(),
+        }
+    }
 }
 
 fn compute_score(c: &Card) -> usize {
-    return c.winning.iter().filter(|w| c.numbers.contains(w)).count();
+    return c.winning.intersection_count(&c.numbers);
 }
 
 // Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
 fn parse_card(i: &str) -> IResult<&str, Card> {
     let (i, _) = tag("Card")(i)?;
     let (i, _) = multispace1(i)?;
-    let (i, id) = i32(i)?;
+    let (i, id) = u32(i)?;
     let (i, _) = tag(":")(i)?;
     let (i, _) = multispace1(i)?;
 
@@ -34,51 +99,351 @@ fn parse_card(i: &str) -> IResult<&str, Card> {
         multispace1, 
         i32)(i)?;
 
-    Ok((i, Card {
-        winning: HashSet::from_iter(winning.into_iter()), 
-        numbers: HashSet::from_iter(numbers.into_iter()) 
-    }))
+    Ok((i, Card::new(id, &winning, &numbers)))
+}
+
+/// Points for a card with a given number of matches.
+pub trait PointsRule {
+    fn points(&self, matches: usize) -> u64;
+}
+
+/// One point for the first match, doubled for each match after that, saturating
+/// rather than overflowing.
+pub struct Doubling;
+
+impl PointsRule for Doubling {
+    fn points(&self, matches: usize) -> u64 {
+        if matches == 0 {
+            return 0;
+        } else {
+            return u32::try_from(matches - 1).ok().and_then(|n| 1u64.checked_shl(n)).unwrap_or(u64::MAX);
+        }
+    }
+}
+
+/// The same number of points for every match.
+pub struct Linear(pub u64);
+
+impl PointsRule for Linear {
+    fn points(&self, matches: usize) -> u64 {
+        self.0 * matches as u64
+    }
 }
 
-pub fn run_day4() {
-    println!("Day 1!");
+/// Points looked up by match count, with the last entry used for any count past
+/// the end of the table.
+pub struct Table(pub Vec<u64>);
 
-    let mut f = File::open("data/day4.txt").expect("File not found");
-    let mut s = String::new();
-    f.read_to_string(&mut s).expect("Unable to load file");
+impl PointsRule for Table {
+    fn points(&self, matches: usize) -> u64 {
+        self.0.get(matches).or(self.0.last()).copied().unwrap_or(0)
+    }
+}
 
-    let lines = s.lines().filter(|s| !s.is_empty()).collect::<Vec<_>>();
+impl<F: Fn(usize) -> u64> PointsRule for F {
+    fn points(&self, matches: usize) -> u64 {
+        self(matches)
+    }
+}
 
-    println!("Loaded {} lines", lines.len());
+/// The total points for all of the cards under `rule`. Like `Doubling`, the total
+/// saturates at `u64::MAX` rather than overflowing.
+pub fn total_points(cards: &[Card], rule: &dyn PointsRule) -> u64 {
+    cards.iter().fold(0, |total, c| total.saturating_add(rule.points(compute_score(c))))
+}
 
-    let cards = lines.iter().map(|l| parse_card(l).expect("Unable to parse card").1).collect::<Vec<_>>();
+/// Which cards a card wins a copy of, by ID, for each copy of it held. IDs are
+/// signed so rules can name cards that can't exist.
+pub trait CopyRule {
+    fn won(&self, card: u32, matches: usize) -> Vec<i64>;
+}
 
-    let score: u32 = cards.iter().map(
-        |c| {
-        let score = compute_score(c);
+/// Copies of the cards with the next `matches` IDs.
+pub struct NextCards;
 
-        if score == 0 {
-            return 0;
-        } else {
-            return 2_u32.pow((score - 1).try_into().unwrap());
-        }
-    }).sum();
+impl CopyRule for NextCards {
+    fn won(&self, card: u32, matches: usize) -> Vec<i64> {
+        (1..=matches as i64).map(|n| card as i64 + n).collect()
+    }
+}
 
-    println!("Part 1: {}", score);
+/// Copies of the cards with the previous `matches` IDs.
+pub struct PreviousCards;
 
-    let mut copies = vec!(1 ; cards.len());
+impl CopyRule for PreviousCards {
+    fn won(&self, card: u32, matches: usize) -> Vec<i64> {
+        (1..=matches as i64).map(|n| card as i64 - n).collect()
+    }
+}
+
+/// What to do when a card wins copies of cards that don't exist.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum OutOfRange {
+    /// Win copies of the cards that do exist and drop the rest.
+    Clamp,
+    /// Stop with an error naming the first missing card.
+    Error,
+    /// The card wins nothing at all.
+    Ignore,
+}
+
+/// How a card fared in the cascade.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CardCopies {
+    pub id: u32,
+    /// The original card plus every copy won.
+    pub copies: u64,
+    /// How many copies of other cards this card and its copies won.
+    pub produced: u64,
+}
+
+/// Plays out the cascade of copies, where each copy of a card wins a copy of the
+/// cards `rule` picks. A card is resolved once every card that wins copies of it
+/// has been, lowest ID first, so the rule can point either way but not in a
+/// cycle. The breakdown is in ID order, and counts too large for a `u64` are an
+/// error.
+pub fn cascade(cards: &[Card], rule: &dyn CopyRule, policy: OutOfRange) -> Result<Vec<CardCopies>> {
+    let mut order = (0..cards.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| cards[i].id);
+
+    let mut positions = HashMap::new();
 
-    for i in 0..cards.len() {
+    for (position, &i) in order.iter().enumerate() {
+        if positions.insert(cards[i].id, position).is_some() {
+            return Err(Error::DuplicateCard { day: 4, card: cards[i].id });
+        }
+    }
+
+    let mut targets = Vec::with_capacity(cards.len());
+    let mut winners = vec!(0; cards.len());
+
+    for &i in order.iter() {
         let card = &cards[i];
-        let amount = copies[i];
-        let score = compute_score(card);
+        let won = rule.won(card.id, compute_score(card)).into_iter()
+            .map(|id| (id, u32::try_from(id).ok().and_then(|id| positions.get(&id)).copied()));
+
+        let won = match policy {
+            OutOfRange::Clamp => won.filter_map(|(_, p)| p).collect::<Vec<_>>(),
+            OutOfRange::Ignore => won.map(|(_, p)| p).collect::<Option<Vec<_>>>().unwrap_or_default(),
+            OutOfRange::Error => won
+                .map(|(id, p)| p.ok_or(Error::CardOutOfRange { day: 4, card: card.id, won: id }))
+                .collect::<Result<Vec<_>>>()?,
+        };
+
+        for &target in won.iter() {
+            winners[target] += 1;
+        }
+
+        targets.push(won);
+    }
+
+    let mut breakdown = order.iter()
+        .map(|&i| CardCopies { id: cards[i].id, copies: 1, produced: 0 })
+        .collect::<Vec<_>>();
+
+    let mut ready = (0..cards.len()).filter(|&p| winners[p] == 0).map(Reverse).collect::<BinaryHeap<_>>();
+    let mut resolved = 0;
+
+    while let Some(Reverse(position)) = ready.pop() {
+        let copies = breakdown[position].copies;
+
+        for &target in targets[position].iter() {
+            breakdown[target].copies = breakdown[target].copies.checked_add(copies)
+                .ok_or(Error::TooManyCopies { day: 4, card: breakdown[target].id })?;
+            winners[target] -= 1;
+
+            if winners[target] == 0 {
+                ready.push(Reverse(target));
+            }
+        }
+
+        breakdown[position].produced = copies.checked_mul(targets[position].len() as u64)
+            .ok_or(Error::TooManyCopies { day: 4, card: breakdown[position].id })?;
+        resolved += 1;
+    }
+
+    if resolved < cards.len() {
+        let stuck = (0..cards.len()).find(|&p| winners[p] > 0).expect("Some card is unresolved");
+
+        return Err(Error::CardCycle { day: 4, card: breakdown[stuck].id });
+    }
+
+    Ok(breakdown)
+}
+
+pub struct Day4;
+
+impl Solution for Day4 {
+    type Input = Vec<Card>;
+    type Part1 = u64;
+    type Part2 = u64;
+
+    fn day(&self) -> u8 {
+        4
+    }
+
+    fn parse(&self, s: &str) -> Result<Vec<Card>> {
+        parse_lines(self.day(), s, parse_card)
+    }
+
+    fn part1(&self, cards: &Vec<Card>) -> Result<u64> {
+        Ok(total_points(cards, &Doubling))
+    }
+
+    fn part2(&self, cards: &Vec<Card>) -> Result<u64> {
+        // The puzzle promises no card wins past the end of the table
+        cascade(cards, &NextCards, OutOfRange::Error)?.iter()
+            .try_fold(0u64, |total, c| total.checked_add(c.copies).ok_or(Error::TooManyCopies { day: 4, card: c.id }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::XorShift;
+
+    use super::*;
+
+    const EXAMPLE: &str = "\
+Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
+";
+
+    #[test]
+    fn test_number_set() {
+        let set = [3, 0, 127, 128, 255, 256, -1, 1000].into_iter().collect::<NumberSet>();
+
+        assert!([3, 0, 127, 128, 255, 256, -1, 1000].iter().all(|&n| set.contains(n)));
+        assert!(![1, 129, 254, 257, -2, i32::MIN].iter().any(|&n| set.contains(n)));
+
+        let other = [0, 128, 256, -1, 7, -7].into_iter().collect::<NumberSet>();
+
+        assert_eq!(set.intersection_count(&other), 4);
+        assert_eq!(other.intersection_count(&set), 4);
+        assert_eq!(set.intersection_count(&NumberSet::default()), 0);
+    }
+
+    #[test]
+    fn test_number_set_matches_hashing() {
+        let mut rng = XorShift::new();
+
+        for _ in 0..1000 {
+            // Mostly in the bitset's range, with some either side of it
+            let mut numbers = || (0..20).map(|_| (rng.next_u64() % 320) as i32 - 32).collect::<Vec<_>>();
+            let (winning, numbers) = (numbers(), numbers());
+
+            let expected = winning.iter().collect::<HashSet<_>>().intersection(&numbers.iter().collect()).count();
 
-        for n in (i+1)..(i+1+score) {
-            copies[n] += amount;
+            assert_eq!(compute_score(&Card::new(1, &winning, &numbers)), expected);
         }
     }
 
-    let total_cards: i32 = copies.iter().sum();
+    fn copies(breakdown: Vec<CardCopies>) -> Vec<(u32, u64, u64)> {
+        breakdown.iter().map(|c| (c.id, c.copies, c.produced)).collect()
+    }
+
+    #[test]
+    fn test_cascade() {
+        let cards = Day4.parse(EXAMPLE).expect("Unable to parse cards");
+
+        assert_eq!(
+            cascade(&cards, &NextCards, OutOfRange::Error).map(copies),
+            Ok(vec!((1, 1, 4), (2, 2, 4), (3, 4, 8), (4, 8, 8), (5, 14, 0), (6, 1, 0))));
+        assert_eq!(Day4.part2(&cards), Ok(30));
+    }
+
+    /// A table of `length` cards that each win the next two, apart from the last two,
+    /// so the copies grow like the Fibonacci numbers.
+    fn chain(length: u32) -> Vec<Card> {
+        (1..=length).map(|id| match length - id {
+            0 => Card::new(id, &[], &[]),
+            1 => Card::new(id, &[1], &[1]),
+            _ => Card::new(id, &[1, 2], &[1, 2]),
+        }).collect()
+    }
+
+    #[test]
+    fn test_cascade_overflow() {
+        assert_eq!(cascade(&chain(100), &NextCards, OutOfRange::Error), Err(Error::TooManyCopies { day: 4, card: 92 }));
+
+        // Every card's copies fit, but not their total
+        assert!(cascade(&chain(90), &NextCards, OutOfRange::Error).is_ok());
+        assert_eq!(Day4.part2(&chain(90)), Err(Error::TooManyCopies { day: 4, card: 90 }));
+        assert!(Day4.part2(&chain(80)).is_ok());
+    }
+
+    #[test]
+    fn test_cascade_by_id() {
+        // Out of order, and card 9 wins copies of cards 10 and 11 which don't exist
+        let cards = Day4.parse("\
+Card 9: 1 2 | 1 2
+Card 7: 1 | 1
+Card 8: 1 | 3
+").expect("Unable to parse cards");
+
+        assert_eq!(
+            cascade(&cards, &NextCards, OutOfRange::Clamp).map(copies),
+            Ok(vec!((7, 1, 1), (8, 2, 0), (9, 1, 0))));
+        assert_eq!(
+            cascade(&cards, &NextCards, OutOfRange::Ignore).map(copies),
+            Ok(vec!((7, 1, 1), (8, 2, 0), (9, 1, 0))));
+        assert_eq!(
+            cascade(&cards, &NextCards, OutOfRange::Error),
+            Err(Error::CardOutOfRange { day: 4, card: 9, won: 10 }));
+
+        // Card 2 wins copies of card 3, which exists, and card 4, which doesn't
+        let cards = Day4.parse("Card 2: 5 6 | 5 6\nCard 3: 1 | 2\n").expect("Unable to parse cards");
+
+        assert_eq!(cascade(&cards, &NextCards, OutOfRange::Clamp).map(copies), Ok(vec!((2, 1, 1), (3, 2, 0))));
+        assert_eq!(cascade(&cards, &NextCards, OutOfRange::Ignore).map(copies), Ok(vec!((2, 1, 0), (3, 1, 0))));
+
+        let cards = Day4.parse("Card 1: 1 | 2\nCard 1: 1 | 2\n").expect("Unable to parse cards");
+
+        assert_eq!(cascade(&cards, &NextCards, OutOfRange::Clamp), Err(Error::DuplicateCard { day: 4, card: 1 }));
+    }
+
+    #[test]
+    fn test_points_rules() {
+        let cards = Day4.parse(EXAMPLE).expect("Unable to parse cards");
+
+        // The example cards have 4, 2, 2, 1, 0 and 0 matches
+        assert_eq!(total_points(&cards, &Doubling), 13);
+        assert_eq!(total_points(&cards, &Linear(3)), 27);
+        assert_eq!(total_points(&cards, &Table(vec!(0, 1, 5))), 16);
+        assert_eq!(total_points(&cards, &|m: usize| (m * m) as u64), 25);
+        assert_eq!(Doubling.points(40), 1 << 39);
+        assert_eq!(Doubling.points(65), u64::MAX);
+
+        let numbers = (1..=70).collect::<Vec<i32>>();
+        let lucky = vec!(Card::new(1, &numbers, &numbers), Card::new(2, &numbers, &numbers));
+
+        assert_eq!(total_points(&lucky, &Doubling), u64::MAX);
+        assert_eq!(total_points(&cards, &|_: usize| u64::MAX / 4), u64::MAX);
+    }
+
+    #[test]
+    fn test_copy_rules() {
+        let cards = Day4.parse(EXAMPLE).expect("Unable to parse cards");
+
+        assert_eq!(
+            cascade(&cards, &PreviousCards, OutOfRange::Clamp).map(copies),
+            Ok(vec!((1, 6, 0), (2, 3, 3), (3, 2, 4), (4, 1, 1), (5, 1, 0), (6, 1, 0))));
+        assert_eq!(
+            cascade(&cards, &PreviousCards, OutOfRange::Error),
+            Err(Error::CardOutOfRange { day: 4, card: 1, won: 0 }));
+
+        // Cards 1 and 2 win copies of each other, so the copies never stop
+        struct Swap;
 
-    println!("Part 2: {}", total_cards);
-}
\ No newline at end of file
+        impl CopyRule for Swap {
+            fn won(&self, card: u32, _: usize) -> Vec<i64> {
+                vec!(if card % 2 == 1 { card as i64 + 1 } else { card as i64 - 1 })
+            }
+        }
+
+        assert_eq!(cascade(&cards, &Swap, OutOfRange::Clamp), Err(Error::CardCycle { day: 4, card: 1 }));
+    }
+}
@@ -1,13 +1,14 @@
-use std::fs::File;
-use std::io::Read;
+use std::ops::Range;
 
 use nom::IResult;
-use nom::branch::alt;
 use nom::bytes::complete::tag;
 use nom::character::complete::{u64, alpha1, newline};
-use nom::combinator::value;
 use nom::multi::{separated_list1, many1};
 
+use crate::error::{Error, Result};
+use crate::parse::parse_all;
+use crate::solution::Solution;
+
 #[derive(Debug)]
 struct MapEntry {
     dest_start: u64,
@@ -15,48 +16,144 @@ struct MapEntry {
     length: u64,
 }
 
+impl MapEntry {
+    fn source_end(&self) -> u64 {
+        self.source_start + self.length
+    }
+}
+
 #[derive(Debug)]
 struct Map {
     from: String,
     to: String,
+    // Sorted by `source_start`, empty entries removed.
     entries: Vec<MapEntry>
 }
 
 impl Map {
+    fn new(from: String, to: String, mut entries: Vec<MapEntry>) -> Map {
+        entries.retain(|e| e.length > 0);
+        entries.sort_by_key(|e| e.source_start);
+
+        Map { from: from, to: to, entries: entries }
+    }
+
+    /// Rejects maps where a value could be covered by more than one entry.
+    fn check_overlaps(&self) -> Result<()> {
+        for pair in self.entries.windows(2) {
+            if pair[0].source_end() > pair[1].source_start {
+                return Err(Error::OverlappingRanges {
+                    day: 5,
+                    map: format!("{}-to-{}", self.from, self.to),
+                    first: pair[0].source_start..pair[0].source_end(),
+                    second: pair[1].source_start..pair[1].source_end(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Index of the first entry that ends after `val`.
+    fn first_entry_ending_after(&self, val: u64) -> usize {
+        self.entries.partition_point(|e| e.source_end() <= val)
+    }
+
     fn transform(&self, val: u64) -> u64 {
-        for entry in self.entries.iter() {
-            if val > entry.source_start && val < entry.source_start + entry.length {
-                return entry.dest_start + (val - entry.source_start);
+        match self.entries.get(self.first_entry_ending_after(val)) {
+            Some(entry) if entry.source_start <= val => entry.dest_start + (val - entry.source_start),
+            _ => val,
+        }
+    }
+
+    /// Maps a whole range of values at once, splitting it wherever it crosses the
+    /// edge of an entry. Parts of the range not covered by any entry map to themselves.
+    fn transform_range(&self, range: Range<u64>) -> Vec<Range<u64>> {
+        let mut mapped = Vec::new();
+        let mut start = range.start;
+
+        for entry in self.entries[self.first_entry_ending_after(range.start)..].iter() {
+            let source_end = entry.source_end();
+
+            if entry.source_start >= range.end {
+                break;
             }
+
+            if start < entry.source_start {
+                mapped.push(start..entry.source_start);
+                start = entry.source_start;
+            }
+
+            let end = source_end.min(range.end);
+
+            if start < end {
+                mapped.push(
+                    (entry.dest_start + (start - entry.source_start))..(entry.dest_start + (end - entry.source_start)));
+                start = end;
+            }
+        }
+
+        if start < range.end {
+            mapped.push(start..range.end);
         }
 
-        return val;
+        mapped
     }
 }
 
 #[derive(Debug)]
-struct Almanac {
+pub struct Almanac {
     seeds: Vec<u64>,
     maps: Vec<Map>,
 }
 
 impl Almanac {
-    fn transform(&self, seed: u64) -> u64 {
+    fn map_from(&self, category: &str) -> Result<&Map> {
+        self.maps.iter().find(
+            |m| m.from == category
+        ).ok_or(Error::MissingMap { day: 5, category: category.to_string() })
+    }
+
+    fn transform(&self, seed: u64) -> Result<u64> {
         let mut current_type = "seed".to_string();
         let required_type = "location".to_string();
         let mut val = seed;
 
         while current_type != required_type {
-            // Find the map for the current type
-            let map = self.maps.iter().find(
-                |m| m.from == current_type
-            ).expect("Unable to find source map");
+            let map = self.map_from(&current_type)?;
 
             val = map.transform(val);
             current_type = map.to.clone();
         }
 
-        return val;
+        return Ok(val);
+    }
+
+    fn transform_ranges(&self, seeds: Vec<Range<u64>>) -> Result<Vec<Range<u64>>> {
+        let mut current_type = "seed".to_string();
+        let required_type = "location".to_string();
+        let mut ranges = seeds;
+
+        while current_type != required_type {
+            let map = self.map_from(&current_type)?;
+
+            ranges = ranges.into_iter().flat_map(|r| map.transform_range(r)).collect();
+            current_type = map.to.clone();
+        }
+
+        return Ok(ranges);
+    }
+
+    /// Part 2 reads the seeds as (start, length) pairs.
+    fn seed_ranges(&self) -> Result<Vec<Range<u64>>> {
+        if self.seeds.len() % 2 == 1 {
+            return Err(Error::UnpairedSeed { day: 5, seed: self.seeds[self.seeds.len() - 1] });
+        }
+
+        Ok(self.seeds.chunks(2)
+            .map(|pair| pair[0]..(pair[0] + pair[1]))
+            .filter(|range| !range.is_empty())
+            .collect())
     }
 }
 
@@ -81,7 +178,7 @@ fn parse_map(i: &str) -> IResult<&str, Map> {
         newline,
         parse_map_entry)(i)?;
 
-    Ok((i, Map { from: from.to_string(), to: to.to_string() , entries: map_entries }))
+    Ok((i, Map::new(from.to_string(), to.to_string(), map_entries)))
 }
 
 fn parse_almanac(i: &str) -> IResult<&str, Almanac> {
@@ -100,18 +197,152 @@ fn parse_almanac(i: &str) -> IResult<&str, Almanac> {
     Ok((i, Almanac { seeds: seeds, maps: maps}))
 }
 
-pub fn run_day5() {
-    println!("Start day 5!");
+pub struct Day5;
+
+impl Solution for Day5 {
+    type Input = Almanac;
+    type Part1 = u64;
+    type Part2 = u64;
+
+    fn day(&self) -> u8 {
+        5
+    }
+
+    fn parse(&self, s: &str) -> Result<Almanac> {
+        let almanac = parse_all(self.day(), s, parse_almanac)?;
+
+        for map in almanac.maps.iter() {
+            map.check_overlaps()?;
+        }
+
+        Ok(almanac)
+    }
+
+    fn part1(&self, almanac: &Almanac) -> Result<u64> {
+        let locations = almanac.seeds.iter().map(|s| almanac.transform(*s)).collect::<Result<Vec<_>>>()?;
+
+        Ok(*locations.iter().min().expect("Almanac has at least one seed"))
+    }
+
+    fn part2(&self, almanac: &Almanac) -> Result<u64> {
+        let locations = almanac.transform_ranges(almanac.seed_ranges()?)?;
+
+        locations.iter().map(|r| r.start).min().ok_or(Error::NoSeeds { day: 5 })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+seeds: 79 14 55 13
+
+seed-to-soil map:
+50 98 2
+52 50 48
 
-    let mut f = File::open("data/day5.txt").expect("File not found");
-    let mut s = String::new();
-    f.read_to_string(&mut s).expect("Unable to load file");
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
 
-    let almanac = parse_almanac(&s).expect("Unable to parse almanac").1;
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
 
-    println!("Loaded almanac: with {} maps", almanac.maps.len());
+water-to-light map:
+88 18 7
+18 25 70
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+humidity-to-location map:
+60 56 37
+56 93 4
+";
+
+    fn seed_to_soil() -> Map {
+        Map::new(
+            "seed".to_string(),
+            "soil".to_string(),
+            vec!(
+                MapEntry { dest_start: 50, source_start: 98, length: 2 },
+                MapEntry { dest_start: 52, source_start: 50, length: 48 },
+            ))
+    }
 
-    let locations = almanac.seeds.iter().map(|s| almanac.transform(*s)).collect::<Vec<_>>();
-    let lowest_location = locations.iter().min().expect("");
-    println!("Lowest location {}", lowest_location);
-}
\ No newline at end of file
+    #[test]
+    fn test_transform_boundaries() {
+        let map = seed_to_soil();
+
+        assert_eq!(map.transform(49), 49);
+        // Entries include their start...
+        assert_eq!(map.transform(50), 52);
+        assert_eq!(map.transform(97), 99);
+        assert_eq!(map.transform(98), 50);
+        assert_eq!(map.transform(99), 51);
+        // ...but not their end.
+        assert_eq!(map.transform(100), 100);
+    }
+
+    #[test]
+    fn test_overlapping_entries_rejected() {
+        let input = EXAMPLE.replace("52 50 48", "52 50 49");
+
+        assert_eq!(
+            Day5.parse(&input).err(),
+            Some(Error::OverlappingRanges { day: 5, map: "seed-to-soil".to_string(), first: 50..99, second: 98..100 }));
+    }
+
+    #[test]
+    fn test_transform_range() {
+        let map = seed_to_soil();
+
+        assert_eq!(map.transform_range(0..10), vec!(0..10));
+        assert_eq!(map.transform_range(45..55), vec!(45..50, 52..57));
+        assert_eq!(map.transform_range(95..105), vec!(97..100, 50..52, 100..105));
+        assert_eq!(map.transform_range(60..60), vec!());
+    }
+
+    #[test]
+    fn test_example_part_1() {
+        let almanac = Day5.parse(EXAMPLE).expect("Unable to parse almanac");
+
+        assert_eq!(Day5.part1(&almanac), Ok(35));
+    }
+
+    #[test]
+    fn test_example_part_2() {
+        let almanac = Day5.parse(EXAMPLE).expect("Unable to parse almanac");
+
+        assert_eq!(almanac.seed_ranges(), Ok(vec!(79..93, 55..68)));
+        assert_eq!(Day5.part2(&almanac), Ok(46));
+
+        // Part 1 reads each number as a seed, but part 2 needs them in pairs
+        let almanac = Day5.parse(&EXAMPLE.replace("55 13", "55 13 1")).expect("Unable to parse almanac");
+
+        assert_eq!(Day5.part1(&almanac), Ok(23));
+        assert_eq!(Day5.part2(&almanac), Err(Error::UnpairedSeed { day: 5, seed: 1 }));
+
+        // Ranges of length zero hold no seeds, and when that's all of them there's no answer
+        let almanac = Day5.parse(&EXAMPLE.replace("55 13", "60 0 55 13")).expect("Unable to parse almanac");
+
+        assert_eq!(almanac.seed_ranges(), Ok(vec!(79..93, 55..68)));
+        assert_eq!(Day5.part2(&almanac), Ok(46));
+
+        let almanac = Day5.parse(&EXAMPLE.replace("79 14 55 13", "79 0")).expect("Unable to parse almanac");
+
+        assert_eq!(almanac.seed_ranges(), Ok(vec!()));
+        assert_eq!(Day5.part2(&almanac), Err(Error::NoSeeds { day: 5 }));
+    }
+}
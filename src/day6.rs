@@ -1,43 +1,165 @@
-struct Race {
+use nom::IResult;
+use nom::bytes::complete::tag;
+use nom::character::complete::{digit1, newline, space1};
+use nom::error::{Error, ErrorKind};
+use nom::multi::separated_list1;
+
+use crate::error::Result;
+use crate::parse::parse_all;
+use crate::solution::Solution;
+
+pub struct Race {
     time: u64,
     distance: u64,
 }
 
 impl Race {
+    fn beats_record(&self, hold: u64) -> bool {
+        hold as u128 * (self.time - hold) as u128 > self.distance as u128
+    }
+
+    /// Counts the holds where `hold * (time - hold) > distance`. Those lie strictly
+    /// between the roots of `hold^2 - time * hold + distance`, and the range is
+    /// symmetric about `time / 2`, so only the shortest winning hold is needed.
     fn count_winning_times(&self) -> u64 {
-        let mut winning_times = 0;
+        let time = self.time as u128;
+        let record = 4 * self.distance as u128;
+
+        if time * time <= record {
+            return 0;
+        }
 
-        for hold in 1..self.time {
-            let remaining_time = self.time - hold;
-            let speed = hold;
+        // isqrt rounds down, so the true lower root is at most one below this.
+        let root = (time * time - record).isqrt();
+        let mut shortest = ((time - root) / 2) as u64;
 
-            let distance = speed * remaining_time;
+        if !self.beats_record(shortest) {
+            shortest += 1;
+        }
 
-            if distance >= self.distance {
-                winning_times += 1;
-            }
+        if shortest > self.time - shortest || !self.beats_record(shortest) {
+            return 0;
         }
 
-        winning_times
+        self.time - 2 * shortest + 1
+    }
+}
+
+// Time:      7  15   30
+fn parse_row<'a>(label: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<&'a str>> {
+    move |i| {
+        let (i, _) = tag(label)(i)?;
+        let (i, _) = space1(i)?;
+
+        separated_list1(space1, digit1)(i)
     }
 }
 
-pub fn run_day6() {
-    println!("Start day 6!");
+/// The columns as separate races for part 1, and the same digits with the
+/// spaces removed as a single race for part 2.
+fn parse_races<'a>(i: &'a str) -> IResult<&'a str, (Vec<Race>, Race)> {
+    let (i, times) = parse_row("Time:")(i)?;
+    let (i, _) = newline(i)?;
+    let distance_line = i;
+    let (i, distances) = parse_row("Distance:")(i)?;
 
-    let races = vec!(
-        Race { time: 48, distance: 390 },
-        Race { time: 98, distance: 1103 },
-        Race { time: 90, distance: 1112 },
-        Race { time: 83, distance: 1360 },
-    );
+    let fail = |at| nom::Err::Failure(Error::new(at, ErrorKind::Verify));
 
-    let winning_counts = races.iter().map(|r| r.count_winning_times());
-    let winning_multiple = winning_counts.fold(1, |a, b| a * b);
+    if times.len() != distances.len() {
+        return Err(fail(distance_line));
+    }
 
-    println!("Part 1 result: {}", winning_multiple);
+    // Errors point at the first digit of the number that doesn't fit.
+    let number = |digits: &[&'a str]| digits.concat().parse::<u64>().map_err(|_| fail(digits[0]));
 
-    let big_race = Race { time: 48989083, distance: 390110311121360 };
+    let races = times.iter().zip(distances.iter())
+        .map(|(t, d)| Ok(Race { time: number(&[t])?, distance: number(&[d])? }))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
 
-    println!("Part 2 result: {}", big_race.count_winning_times());
-}
\ No newline at end of file
+    let big_race = Race { time: number(&times)?, distance: number(&distances)? };
+
+    Ok((i, (races, big_race)))
+}
+
+pub struct Day6;
+
+impl Solution for Day6 {
+    type Input = (Vec<Race>, Race);
+    type Part1 = u64;
+    type Part2 = u64;
+
+    fn day(&self) -> u8 {
+        6
+    }
+
+    fn parse(&self, s: &str) -> Result<(Vec<Race>, Race)> {
+        parse_all(self.day(), s, parse_races)
+    }
+
+    fn part1(&self, (races, _): &(Vec<Race>, Race)) -> Result<u64> {
+        let winning_counts = races.iter().map(|r| r.count_winning_times());
+
+        Ok(winning_counts.product::<u64>())
+    }
+
+    fn part2(&self, (_, big_race): &(Vec<Race>, Race)) -> Result<u64> {
+        Ok(big_race.count_winning_times())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::XorShift;
+
+    use super::*;
+
+    fn count_winning_times_brute_force(race: &Race) -> u64 {
+        (0..=race.time).filter(|&hold| race.beats_record(hold)).count() as u64
+    }
+
+    const EXAMPLE: &str = "\
+Time:      7  15   30
+Distance:  9  40  200
+";
+
+    #[test]
+    fn test_parse_races() {
+        let input = Day6.parse(EXAMPLE).expect("Unable to parse races");
+
+        assert_eq!(input.0.len(), 3);
+        assert_eq!(Day6.part1(&input), Ok(288));
+        assert_eq!(Day6.part2(&input), Ok(71503));
+
+        assert!(Day6.parse("Time:      7  15   30\nDistance:  9  40\n").is_err());
+    }
+
+    #[test]
+    fn test_example_races() {
+        assert_eq!(Race { time: 7, distance: 9 }.count_winning_times(), 4);
+        assert_eq!(Race { time: 15, distance: 40 }.count_winning_times(), 8);
+        // Holding for 10 or 20 exactly ties the record, which doesn't count.
+        assert_eq!(Race { time: 30, distance: 200 }.count_winning_times(), 9);
+        assert_eq!(Race { time: 71530, distance: 940200 }.count_winning_times(), 71503);
+    }
+
+    #[test]
+    fn test_perfect_squares() {
+        // time^2 - 4 * distance is 0, 1 and 4 respectively.
+        assert_eq!(Race { time: 10, distance: 25 }.count_winning_times(), 0);
+        assert_eq!(Race { time: 11, distance: 30 }.count_winning_times(), 0);
+        assert_eq!(Race { time: 10, distance: 24 }.count_winning_times(), 1);
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let mut rng = XorShift::new();
+
+        for _ in 0..2000 {
+            let time = rng.next_u64() % 500;
+            let distance = rng.next_u64() % (time * time / 4 + 10);
+            let race = Race { time: time, distance: distance };
+
+            assert_eq!(race.count_winning_times(), count_winning_times_brute_force(&race), "time {} distance {}", time, distance);
+        }
+    }
+}
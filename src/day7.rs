@@ -1,18 +1,18 @@
 use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::Read;
-use std::ops::Index;
 
 use nom::{IResult, InputIter};
-use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::character::complete::{char, u32, alphanumeric1};
-use nom::combinator::value;
-use nom::multi::{separated_list1, many1};
+use nom::character::complete::{one_of, u32};
+use nom::combinator::recognize;
+use nom::multi::count;
+
+use crate::error::Result;
+use crate::parse::parse_lines;
+use crate::solution::Solution;
 
 #[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
-enum HandType {
+pub enum HandType {
     FiveOfAKind,
     FourOfAKind,
     FullHouse,
@@ -22,147 +22,83 @@ enum HandType {
     HighCard,
 }
 
-fn get_handtype(cards: &HashMap<char, u8>) -> HandType {
-    // Five of a kind, where all five cards have the same label: AAAAA
-    if cards.iter().any(|e| *e.1 == 5) {
-        return HandType::FiveOfAKind;
-    }
-
-    // Four of a kind, where four cards have the same label and one card has a different label: AA8AA
-    if cards.iter().any(|e| *e.1 == 4) {
-        return HandType::FourOfAKind;
-    }
-
-    // Full house, where three cards have the same label, and the remaining two cards share a different label: 23332
-    if cards.iter().any(|e| *e.1 == 3) &&
-       cards.iter().any(|e| *e.1 == 2) {
-        return HandType::FullHouse;
-    }
-
-    // Three of a kind, where three cards have the same label, and the remaining two cards are each different from any other card in the hand: TTT98
-    if cards.iter().any(|e| *e.1 == 3) &&
-       cards.iter().any(|e| *e.1 == 1) {
-        return HandType::ThreeOfAKind;
-    }
-
-    // Two pair, where two cards share one label, two other cards share a second label, and the remaining card has a third label: 23432
-    if cards.iter().filter(|e| *e.1 == 2).count() == 2 {
-        return HandType::TwoPair;
-    }
-
-    // One pair, where two cards share one label, and the other three cards have a different label from the pair and each other: A23A4
-    if cards.iter().filter(|e| *e.1 == 2).count() == 1 &&
-       cards.iter().filter(|e| *e.1 == 1).count() == 3 {
-        return HandType::OnePair;
+impl HandType {
+    /// The type of a hand from how many of its cards share each label, largest
+    /// first, so `[3, 2]` is a full house.
+    fn from_signature(signature: &[u8]) -> HandType {
+        match signature {
+            [5] => HandType::FiveOfAKind,
+            [4, 1] => HandType::FourOfAKind,
+            [3, 2] => HandType::FullHouse,
+            [3, 1, 1] => HandType::ThreeOfAKind,
+            [2, 2, 1] => HandType::TwoPair,
+            [2, 1, 1, 1] => HandType::OnePair,
+            [1, 1, 1, 1, 1] => HandType::HighCard,
+            _ => panic!("{:?} is not the signature of a five card hand", signature),
+        }
     }
-
-    // High card, where all cards' labels are distinct: 23456
-    return HandType::HighCard;
 }
 
-fn get_handtype_part_2(cards: &mut HashMap<char, u8>) -> HandType {
-
-    // Five of a kind, where all five cards have the same label: AAAAA
-    if cards.iter().any(|e| *e.1 == 5) {
-        return HandType::FiveOfAKind;
-    }
-
-    // Four of a kind, where four cards have the same label and one card has a different label: AA8AA
-    if cards.iter().any(|e| *e.1 == 4) {
-        return HandType::FourOfAKind;
-    }
-
-    // Full house, where three cards have the same label, and the remaining two cards share a different label: 23332
-    if cards.iter().any(|e| *e.1 == 3) &&
-       cards.iter().any(|e| *e.1 == 2) {
-        return HandType::FullHouse;
-    }
-
-    // Three of a kind, where three cards have the same label, and the remaining two cards are each different from any other card in the hand: TTT98
-    if cards.iter().any(|e| *e.1 == 3) &&
-       cards.iter().any(|e| *e.1 == 1) {
-        return HandType::ThreeOfAKind;
+/// Classifies a hand of five cards. Cards labelled `wildcard` stand in for
+/// whatever makes the best hand, which is always more of the label the other
+/// cards have most of. They're taken out of the counts before joining that label
+/// so they're never counted twice, and a hand of only wildcards is five of a kind.
+fn classify(cards: &[char], wildcard: Option<char>) -> HandType {
+    let mut counts = HashMap::new();
+    let mut wildcards = 0;
+
+    for &card in cards {
+        if Some(card) == wildcard {
+            wildcards += 1;
+        } else {
+            *counts.entry(card).or_insert(0) += 1;
+        }
     }
 
-    // Two pair, where two cards share one label, two other cards share a second label, and the remaining card has a third label: 23432
-    if cards.iter().filter(|e| *e.1 == 2).count() == 2 {
-        return HandType::TwoPair;
-    }
+    let mut signature = counts.into_values().collect::<Vec<u8>>();
+    signature.sort_by(|a, b| b.cmp(a));
 
-    // One pair, where two cards share one label, and the other three cards have a different label from the pair and each other: A23A4
-    if cards.iter().filter(|e| *e.1 == 2).count() == 1 &&
-       cards.iter().filter(|e| *e.1 == 1).count() == 3 {
-        return HandType::OnePair;
+    match signature.first_mut() {
+        Some(most) => *most += wildcards,
+        // Every card is wild
+        None => signature.push(wildcards),
     }
 
-    // High card, where all cards' labels are distinct: 23456
-    return HandType::HighCard;
+    HandType::from_signature(&signature)
 }
 
-const HANDTYPE_RANKINGS: [HandType ; 7] = [
-    HandType::FiveOfAKind, HandType::FourOfAKind, HandType::FullHouse, HandType::ThreeOfAKind, HandType::TwoPair, HandType::OnePair, HandType::HighCard];
+const CARD_ORDER: &str = "AKQJT98765432";
+const CARD_ORDER_PT_2: &str = "AKQT98765432J";
 
-const CARD_ORDER: &'static str = "AKQJT98765432";
-const CARD_ORDER_PT_2: &'static str = "AKQT98765432J";
+/// In part 2 jacks are jokers, which are wild.
+const JOKER: char = 'J';
 
-#[derive(Debug)]
-struct Hand {
+#[derive(Debug, Clone)]
+pub struct Hand {
     cards: Vec<char>,
-    card_counts: HashMap<char, u8>,
     bid: u32,
     hand_type: HandType
 }
 
-fn parse_hand(i: &str) -> IResult<&str, Hand> {
-    let (i, card_chars) = alphanumeric1(i)?;
-    let (i, _) = tag(" ")(i)?;
-    let (i, bid) = u32(i)?;
-
-    let cards = card_chars.chars().collect::<Vec<_>>();
-    let mut card_counts = HashMap::new();
-
-    for card in cards.iter() {
-        if card_counts.contains_key(card) {
-            let mut current_count = card_counts.get_mut(card).expect("");
-            *current_count += 1;
-        } else {
-            card_counts.insert(card.clone(), 1);
-        }
-    }
-
-    let hand_type = get_handtype(&card_counts);
-
-    Ok((i, Hand { cards: cards, card_counts: card_counts, bid: bid, hand_type: hand_type }))
+fn parse_cards(i: &str) -> IResult<&str, &str> {
+    recognize(count(one_of(CARD_ORDER), 5))(i)
 }
 
-fn parse_hand_pt2(i: &str) -> IResult<&str, Hand> {
-    let (i, card_chars) = alphanumeric1(i)?;
-    let (i, _) = tag(" ")(i)?;
-    let (i, bid) = u32(i)?;
+// 32T3K 765
+fn parse_hand(wildcard: Option<char>) -> impl Fn(&str) -> IResult<&str, Hand> {
+    move |i| {
+        let (i, card_chars) = parse_cards(i)?;
+        let (i, _) = tag(" ")(i)?;
+        let (i, bid) = u32(i)?;
 
-    let cards = card_chars.chars().collect::<Vec<_>>();
-    let mut card_counts = HashMap::new();
+        let cards = card_chars.chars().collect::<Vec<_>>();
+        let hand_type = classify(&cards, wildcard);
 
-    for card in cards.iter() {
-        if card_counts.contains_key(card) {
-            let mut current_count = card_counts.get_mut(card).expect("");
-            *current_count += 1;
-        } else {
-            card_counts.insert(card.clone(), 1);
-        }
+        Ok((i, Hand { cards: cards, bid: bid, hand_type: hand_type }))
     }
-
-    let joker_count = card_counts.get(&'J').unwrap_or(&0).clone();
-
-    let highest_card = card_counts.iter_mut().max_by(|x, y| x.1.cmp(&y.1)).expect("");
-    *highest_card.1 = *highest_card.1 + joker_count;
-
-    let hand_type = get_handtype(&mut card_counts);
-
-    Ok((i, Hand { cards: cards, card_counts: card_counts, bid: bid, hand_type: hand_type }))
 }
 
-fn order_hands(hands: &mut Vec<Hand>, card_order: &str) {
+fn order_hands(hands: &mut [Hand], card_order: &str) {
     hands.sort_by(|a, b| {
         let ah = a.hand_type as isize;
         let bh = b.hand_type as isize;
@@ -185,53 +121,58 @@ fn order_hands(hands: &mut Vec<Hand>, card_order: &str) {
     });
 }
 
-fn compute_part_1_score(mut hands: &Vec<Hand>) -> u32 {
+fn compute_part_1_score(hands: &[Hand]) -> u32 {
     (0..hands.len()).map(|i| hands[i].bid * (i + 1) as u32).sum::<u32>()
 }
 
-pub fn run_day7() {
-    println!("Start day 7!");
+pub struct Day7;
+
+impl Solution for Day7 {
+    type Input = (Vec<Hand>, Vec<Hand>);
+    type Part1 = u32;
+    type Part2 = u32;
+
+    fn day(&self) -> u8 {
+        7
+    }
+
+    fn parse(&self, s: &str) -> Result<(Vec<Hand>, Vec<Hand>)> {
+        let hands = parse_lines(self.day(), s, parse_hand(None))?;
+        let hands_2 = parse_lines(self.day(), s, parse_hand(Some(JOKER)))?;
 
-    let mut f = File::open("data/day7.txt").expect("File not found");
-    let mut s = String::new();
-    f.read_to_string(&mut s).expect("Unable to load file");
+        Ok((hands, hands_2))
+    }
 
-    let mut hands = 
-        s.lines().map(|l| 
-            parse_hand(l).expect("Unable to parse hand").1
-    ).collect::<Vec<_>>();
+    fn part1(&self, (hands, _): &(Vec<Hand>, Vec<Hand>)) -> Result<u32> {
+        let mut hands = hands.clone();
 
-    order_hands(&mut hands, CARD_ORDER);
-    hands.reverse();
+        order_hands(&mut hands, CARD_ORDER);
+        hands.reverse();
 
-    let summed_score = compute_part_1_score(&hands);
-    println!("Part 1 score {}", summed_score);
+        Ok(compute_part_1_score(&hands))
+    }
 
-    let mut hands_2 = 
-    s.lines().map(|l| 
-        parse_hand_pt2(l).expect("Unable to parse hand").1
-    ).collect::<Vec<_>>();
+    fn part2(&self, (_, hands_2): &(Vec<Hand>, Vec<Hand>)) -> Result<u32> {
+        let mut hands_2 = hands_2.clone();
 
-    order_hands(&mut hands_2, CARD_ORDER_PT_2);
-    hands_2.reverse();
+        order_hands(&mut hands_2, CARD_ORDER_PT_2);
+        hands_2.reverse();
 
-    let summed_score_2 = compute_part_1_score(&hands_2);
-    println!("Part 2 score {}", summed_score_2);
+        Ok(compute_part_1_score(&hands_2))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const EXAMPLE: &str = include_str!("../tests/fixtures/day7.txt");
+
     #[test]
     fn test_ranking() {
-        let mut f = File::open("data/test_day7.txt").expect("File not found");
-        let mut s = String::new();
-        f.read_to_string(&mut s).expect("Unable to load file");
-    
         let mut hands = 
-            s.lines().map(|l| 
-                parse_hand(l).expect("Unable to parse hand").1
+            EXAMPLE.lines().map(|l| 
+                parse_hand(None)(l).expect("Unable to parse hand").1
         ).collect::<Vec<_>>();
 
         order_hands(&mut hands, CARD_ORDER);
@@ -246,13 +187,9 @@ mod tests {
 
     #[test]
     fn test_part_2() {
-        let mut f = File::open("data/test_day7.txt").expect("File not found");
-        let mut s = String::new();
-        f.read_to_string(&mut s).expect("Unable to load file");
-    
         let mut hands = 
-            s.lines().map(|l| 
-                parse_hand_pt2(l).expect("Unable to parse hand").1
+            EXAMPLE.lines().map(|l| 
+                parse_hand(Some(JOKER))(l).expect("Unable to parse hand").1
         ).collect::<Vec<_>>();
 
         order_hands(&mut hands, CARD_ORDER_PT_2);
@@ -264,4 +201,122 @@ mod tests {
 
         assert_eq!(score, 5905);
     }
-}
\ No newline at end of file
+
+    fn classify_str(cards: &str, wildcard: Option<char>) -> HandType {
+        classify(&cards.chars().collect::<Vec<_>>(), wildcard)
+    }
+
+    #[test]
+    fn test_joker_edge_cases() {
+        assert_eq!(classify_str("JJJJJ", Some(JOKER)), HandType::FiveOfAKind);
+        assert_eq!(classify_str("JJJJ2", Some(JOKER)), HandType::FiveOfAKind);
+        // Jokers in the majority join the other label rather than each other
+        assert_eq!(classify_str("JJJ22", Some(JOKER)), HandType::FiveOfAKind);
+        assert_eq!(classify_str("JJJ23", Some(JOKER)), HandType::FourOfAKind);
+        assert_eq!(classify_str("JJ223", Some(JOKER)), HandType::FourOfAKind);
+        assert_eq!(classify_str("JJ234", Some(JOKER)), HandType::ThreeOfAKind);
+        assert_eq!(classify_str("J2233", Some(JOKER)), HandType::FullHouse);
+        assert_eq!(classify_str("J2345", Some(JOKER)), HandType::OnePair);
+        assert_eq!(classify_str("KTJJT", Some(JOKER)), HandType::FourOfAKind);
+
+        // Without wildcards jacks are just another label
+        assert_eq!(classify_str("JJJJJ", None), HandType::FiveOfAKind);
+        assert_eq!(classify_str("JJJ22", None), HandType::FullHouse);
+        assert_eq!(classify_str("J2345", None), HandType::HighCard);
+    }
+
+    #[test]
+    fn test_joker_ordering() {
+        let mut hands = ["JJJJJ 1", "22222 2", "JJJJ2 3", "2345J 4", "23456 5"].iter()
+            .map(|l| parse_hand(Some(JOKER))(l).expect("Unable to parse hand").1)
+            .collect::<Vec<_>>();
+
+        order_hands(&mut hands, CARD_ORDER_PT_2);
+
+        // Jokers are the weakest card when breaking ties, so JJJJJ is the weakest
+        // five of a kind
+        assert_eq!(hands.iter().map(|h| h.bid).collect::<Vec<_>>(), vec!(2, 3, 1, 4, 5));
+    }
+
+    // Every multiset of five cards, as the labels in CARD_ORDER order
+    fn all_hands() -> Vec<Vec<char>> {
+        let labels = CARD_ORDER.chars().collect::<Vec<_>>();
+        let mut hands = vec!(vec!());
+
+        for _ in 0..5 {
+            hands = hands.into_iter()
+                .flat_map(|hand: Vec<char>| {
+                    let from = hand.last().map_or(0, |c| labels.iter().position(|l| l == c).expect("Is a label"));
+
+                    labels[from..].iter().map(move |&l| {
+                        let mut hand = hand.clone();
+                        hand.push(l);
+                        hand
+                    })
+                })
+                .collect();
+        }
+
+        hands
+    }
+
+    // Classifies from the number of distinct labels and the largest group, without signatures
+    fn reference_type(cards: &[char]) -> HandType {
+        let distinct = CARD_ORDER.chars().filter(|l| cards.contains(l)).count();
+        let most = cards.iter().map(|c| cards.iter().filter(|d| *d == c).count()).max().expect("Hand has cards");
+
+        match (distinct, most) {
+            (1, 5) => HandType::FiveOfAKind,
+            (2, 4) => HandType::FourOfAKind,
+            (2, 3) => HandType::FullHouse,
+            (3, 3) => HandType::ThreeOfAKind,
+            (3, 2) => HandType::TwoPair,
+            (4, 2) => HandType::OnePair,
+            (5, 1) => HandType::HighCard,
+            _ => panic!("{:?} is not a five card hand", cards),
+        }
+    }
+
+    #[test]
+    fn test_classify_every_hand() {
+        let hands = all_hands();
+
+        // 13 labels choose 5 with repetition
+        assert_eq!(hands.len(), 6188);
+
+        for hand in hands.iter() {
+            assert_eq!(classify(hand, None), reference_type(hand), "{:?}", hand);
+        }
+    }
+
+    #[test]
+    fn test_classify_every_hand_with_jokers() {
+        let others = CARD_ORDER.chars().filter(|&c| c != JOKER).collect::<Vec<_>>();
+
+        for hand in all_hands() {
+            // Try every label for every joker and keep the best
+            let mut best = HandType::HighCard;
+            let mut substituted = vec!(hand.clone());
+
+            for i in (0..hand.len()).filter(|&i| hand[i] == JOKER) {
+                substituted = substituted.into_iter()
+                    .flat_map(|h| others.iter().map(move |&o| {
+                        let mut h = h.clone();
+                        h[i] = o;
+                        h
+                    }))
+                    .collect();
+            }
+
+            for h in substituted.iter() {
+                let t = reference_type(h);
+
+                if t < best {
+                    best = t;
+                }
+            }
+
+            assert_eq!(classify(&hand, Some(JOKER)), best, "{:?}", hand);
+        }
+    }
+}
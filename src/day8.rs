@@ -1,12 +1,14 @@
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::Read;
 use num::Integer;
 
 use nom::{IResult, character::complete::{alphanumeric1, newline}, multi::{separated_list1, many1}, bytes::complete::tag, sequence::separated_pair};
 
+use crate::error::{Error, Result};
+use crate::parse::parse_all;
+use crate::solution::Solution;
+
 #[derive(Debug)]
-struct Map {
+pub struct Map {
     moves: Vec<char>,
 
     nodes: HashMap<String, (String, String)>
@@ -38,12 +40,18 @@ fn parse_map(i: &str) -> IResult<&str, Map> {
     return Ok((i, Map { moves: raw_moves.chars().collect::<Vec<_>>(), nodes: map }));
 }
 
-fn compute_length_part_1(map: &Map, from: String, to: String) -> usize {
+impl Map {
+    fn node(&self, id: &str) -> Result<&(String, String)> {
+        self.nodes.get(id).ok_or(Error::MissingNode { day: 8, node: id.to_string() })
+    }
+}
+
+fn compute_length_part_1(map: &Map, from: String, to: String) -> Result<usize> {
     let mut move_count = 0;
     let mut current_node = from;
 
     while current_node != to {
-        let node = map.nodes.get(&current_node).expect("Unable to find node");
+        let node = map.node(&current_node)?;
         let m = map.moves.get(move_count % map.moves.len()).expect("");
 
         if *m == 'L' {
@@ -55,94 +63,272 @@ fn compute_length_part_1(map: &Map, from: String, to: String) -> usize {
         move_count += 1;
     }
 
-    move_count
+    Ok(move_count)
 }
 
-fn compute_length_part_2(map: &Map, from: String) -> usize {
-    let mut move_count = 0;
+/// How a ghost moves once it has settled into a loop. A ghost's state is its node
+/// together with its position in the move list, so it must repeat eventually.
+#[derive(Debug, PartialEq)]
+struct GhostCycle {
+    /// Steps taken before the ghost first enters its loop.
+    tail: u64,
+    /// Number of steps in the loop.
+    length: u64,
+    /// Steps before the loop at which the ghost is on a Z node. These only happen once.
+    tail_hits: Vec<u64>,
+    /// Steps in `tail..tail + length` at which the ghost is on a Z node, repeating every `length` steps.
+    cycle_hits: Vec<u64>,
+}
+
+impl GhostCycle {
+    fn is_on_z(&self, step: u64) -> bool {
+        if step < self.tail {
+            self.tail_hits.contains(&step)
+        } else {
+            self.cycle_hits.contains(&(self.tail + (step - self.tail) % self.length))
+        }
+    }
+}
+
+fn analyze_ghost(map: &Map, from: &str) -> Result<GhostCycle> {
+    let mut seen = HashMap::new();
+    let mut hits = Vec::new();
     let mut current_node = from;
+    let mut step = 0;
 
-    while !current_node.ends_with("Z") {
-        let node = map.nodes.get(&current_node).expect("Unable to find node");
-        let m = map.moves.get(move_count % map.moves.len()).expect("");
+    loop {
+        let move_index = step as usize % map.moves.len();
 
-        if *m == 'L' {
-            current_node = node.0.clone();
+        if let Some(&first_seen) = seen.get(&(current_node, move_index)) {
+            let (tail_hits, cycle_hits) = hits.into_iter().partition(|&h| h < first_seen);
+
+            return Ok(GhostCycle { tail: first_seen, length: step - first_seen, tail_hits: tail_hits, cycle_hits: cycle_hits });
+        }
+
+        seen.insert((current_node, move_index), step);
+
+        if current_node.ends_with("Z") {
+            hits.push(step);
+        }
+
+        let node = map.node(current_node)?;
+
+        if map.moves[move_index] == 'L' {
+            current_node = &node.0;
         } else {
-            current_node = node.1.clone();
+            current_node = &node.1;
         }
 
-        move_count += 1;
+        step += 1;
     }
+}
 
-    move_count
+/// Merges `n = a.0 (mod a.1)` and `n = b.0 (mod b.1)` into a single congruence, if they agree anywhere.
+fn combine_congruences(a: (i128, i128), b: (i128, i128)) -> Option<(i128, i128)> {
+    let gcd = a.1.extended_gcd(&b.1);
+
+    if (b.0 - a.0) % gcd.gcd != 0 {
+        return None;
+    }
+
+    let modulus = a.1 / gcd.gcd * b.1;
+    let k = ((b.0 - a.0) / gcd.gcd * gcd.x).mod_floor(&(b.1 / gcd.gcd));
+
+    Some(((a.0 + a.1 * k).mod_floor(&modulus), modulus))
 }
 
-pub fn run_day8() {
-    println!("Start day 8!");
+/// Finds the first step at which every ghost is on a Z node at once.
+fn first_common_step(ghosts: &[GhostCycle]) -> Result<u64> {
+    let settled = ghosts.iter().map(|g| g.tail).max().ok_or(Error::NoStartNodes { day: 8 })?;
 
-    let mut f = File::open("data/day8.txt").expect("File not found");
-    let mut s = String::new();
-    f.read_to_string(&mut s).expect("Unable to load file");
+    // Before every ghost is in its loop the answer has to be one of the one-off
+    // hits of whichever ghost takes longest to settle.
+    let early = ghosts.iter()
+        .filter(|g| g.tail == settled)
+        .flat_map(|g| g.tail_hits.iter())
+        .filter(|&&step| ghosts.iter().all(|g| g.is_on_z(step)))
+        .min();
 
-    let map = parse_map(&s).expect("Unable to parse map").1;
+    if let Some(&step) = early {
+        return Ok(step);
+    }
 
-    let mut current_node = "AAA".to_string();
-    let destination_node = "ZZZ".to_string();
-   
-    let part_1_length = 
-        compute_length_part_1(
-            &map, 
-            "AAA".to_string(),
-            "ZZZ".to_string()
-        );
+    // After that each ghost is periodic, so try every combination of loop hits
+    // and solve the resulting congruences with the Chinese Remainder Theorem.
+    let mut congruences = vec!((0_i128, 1_i128));
 
-    println!("Part one result: {}", part_1_length);
+    for ghost in ghosts {
+        congruences = congruences.iter()
+            .flat_map(|&c| ghost.cycle_hits.iter().filter_map(move |&hit|
+                combine_congruences(c, (hit as i128, ghost.length as i128))))
+            .collect();
+    }
+
+    let step = congruences.iter()
+        .map(|&(residue, modulus)| {
+            let settled = settled as i128;
+            if residue >= settled {
+                residue
+            } else {
+                residue + (settled - residue + modulus - 1) / modulus * modulus
+            }
+        })
+        .min()
+        .ok_or(Error::NoCommonStep { day: 8 })?;
+
+    u64::try_from(step).map_err(|_| Error::StepTooLarge { day: 8 })
+}
+
+pub struct Day8;
+
+impl Solution for Day8 {
+    type Input = Map;
+    type Part1 = usize;
+    type Part2 = u64;
 
-    if part_1_length != 22411 {
-        panic!("Unexpected result for day 8 part 1");
+    fn day(&self) -> u8 {
+        8
     }
 
-    let mut current_nodes = 
-        map.nodes.iter()
-            .filter(
-                |n| 
-                    n.0.ends_with("A"))
-            .map(|n| 
-                compute_length_part_2(&map, n.0.clone()) as i32).collect::<Vec<_>>();
+    fn parse(&self, s: &str) -> Result<Map> {
+        parse_all(self.day(), s, parse_map)
+    }
 
-  //  let part_2_length = current_nodes.into_iter().reduce(|acc, steps|  acc.lcm(&steps)).unwrap();
+    fn part1(&self, map: &Map) -> Result<usize> {
+        let part_1_length = 
+            compute_length_part_1(
+                map, 
+                "AAA".to_string(),
+                "ZZZ".to_string()
+            )?;
 
-  //  println!("Part two result: {}", part_2_length);
+        Ok(part_1_length)
+    }
 
+    fn part2(&self, map: &Map) -> Result<u64> {
+        let ghosts = 
+            map.nodes.keys()
+                .filter(|n| n.ends_with("A"))
+                .map(|n| analyze_ghost(map, n))
+                .collect::<Result<Vec<_>>>()?;
 
-/*
-    move_count = 0;
-    move_index = 0;
-    
+        first_common_step(&ghosts)
+    }
+}
 
-    while !current_nodes.iter().all(|n| n.ends_with("Z")) {
-        let m = map.moves.get(move_index).expect("");
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_1: &str = "\
+RL
+
+AAA = (BBB, CCC)
+BBB = (DDD, EEE)
+CCC = (ZZZ, GGG)
+DDD = (DDD, DDD)
+EEE = (EEE, EEE)
+GGG = (GGG, GGG)
+ZZZ = (ZZZ, ZZZ)
+";
+
+    const EXAMPLE_2: &str = "\
+LR
+
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+22A = (22B, XXX)
+22B = (22C, 22C)
+22C = (22Z, 22Z)
+22Z = (22B, 22B)
+XXX = (XXX, XXX)
+";
+
+    #[test]
+    fn test_part_1_example() {
+        let map = Day8.parse(EXAMPLE_1).expect("Unable to parse map");
+
+        assert_eq!(compute_length_part_1(&map, "AAA".to_string(), "ZZZ".to_string()), Ok(2));
+    }
 
-        current_nodes = current_nodes.iter().map(|current_node| {
-            let node = map.nodes.get(current_node).expect("Unable to find node");
+    #[test]
+    fn test_part_2_example() {
+        let map = Day8.parse(EXAMPLE_2).expect("Unable to parse map");
+
+        assert_eq!(Day8.part2(&map), Ok(6));
+        assert_eq!(
+            analyze_ghost(&map, "11A"),
+            Ok(GhostCycle { tail: 1, length: 2, tail_hits: vec!(), cycle_hits: vec!(2) }));
+        assert_eq!(
+            analyze_ghost(&map, "22A"),
+            Ok(GhostCycle { tail: 1, length: 6, tail_hits: vec!(), cycle_hits: vec!(3, 6) }));
+    }
 
-            if *m == 'L' {
-                return node.0.clone();
-            } else {
-                return node.1.clone();
-            }
-        }).collect();
+    #[test]
+    fn test_part_2_offset_cycles() {
+        // 1A reaches 1Z on every even step, 2A on steps 1, 4, 7, ... which LCM alone gets wrong.
+        let map = Day8.parse("\
+L
+
+1A = (1B, 1B)
+1B = (1Z, 1Z)
+1Z = (1B, 1B)
+2A = (2Z, 2Z)
+2Z = (2B, 2B)
+2B = (2C, 2C)
+2C = (2Z, 2Z)
+").expect("Unable to parse map");
+
+        assert_eq!(Day8.part2(&map), Ok(4));
+    }
 
-        move_count += 1;
-        move_index += 1;
+    #[test]
+    fn test_part_2_tail_hit() {
+        // 2A passes through 2Z once on its way into a loop without any Z nodes.
+        let map = Day8.parse("\
+L
 
-        if move_index == map.moves.len() {
-            move_index = 0;
-        }
+1A = (1Z, 1Z)
+1Z = (1Z, 1Z)
+2A = (2Z, 2Z)
+2Z = (2B, 2B)
+2B = (2B, 2B)
+").expect("Unable to parse map");
+
+        assert_eq!(Day8.part2(&map), Ok(1));
+    }
+
+    #[test]
+    fn test_part_2_no_common_step() {
+        let map = Day8.parse("\
+L
+
+1A = (1B, 1B)
+1B = (1Z, 1Z)
+1Z = (1B, 1B)
+2A = (2Z, 2Z)
+2Z = (2B, 2B)
+2B = (2Z, 2Z)
+").expect("Unable to parse map");
+
+        assert_eq!(Day8.part2(&map), Err(Error::NoCommonStep { day: 8 }));
     }
 
-    println!("Part two result: {}", move_count);
-    */
+    #[test]
+    fn test_part_2_no_start_nodes() {
+        let map = Day8.parse("L\n\n1B = (1Z, 1Z)\n1Z = (1B, 1B)\n").expect("Unable to parse map");
+
+        assert_eq!(Day8.part2(&map), Err(Error::NoStartNodes { day: 8 }));
+    }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_step_too_large() {
+        // Each ghost is a step short of finishing its loop, so they all meet a step
+        // before the product of the two prime loop lengths, which is past u64::MAX
+        let ghost = |length: u64| GhostCycle { tail: 0, length: length, tail_hits: vec!(), cycle_hits: vec!(length - 1) };
+
+        assert_eq!(first_common_step(&[ghost((1 << 61) - 1), ghost((1 << 31) - 1)]), Err(Error::StepTooLarge { day: 8 }));
+        assert_eq!(first_common_step(&[ghost(7), ghost(11)]), Ok(76));
+    }
+}
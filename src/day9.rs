@@ -1,7 +1,12 @@
-use std::fs::File;
-use std::io::Read;
+use nom::bytes::complete::tag;
+use nom::character::complete::i32;
+use nom::multi::separated_list1;
 
-fn extrapolate(seq: &Vec<i32>) -> i32 {
+use crate::error::Result;
+use crate::parse::parse_lines;
+use crate::solution::Solution;
+
+fn extrapolate(seq: &[i32]) -> i32 {
     let diffs = 
         seq.iter()
             .zip(seq.iter().skip(1))
@@ -15,7 +20,7 @@ fn extrapolate(seq: &Vec<i32>) -> i32 {
     }
 }
 
-fn extrapolate_beginning(seq: &Vec<i32>) -> i32 {
+fn extrapolate_beginning(seq: &[i32]) -> i32 {
      let diffs = 
          seq.iter()
              .zip(seq.iter().skip(1))
@@ -29,31 +34,32 @@ fn extrapolate_beginning(seq: &Vec<i32>) -> i32 {
      }
  }
 
-pub fn run_day9() {
-    println!("Run day 9!");
-
-    let mut f = File::open("data/day9.txt").expect("File not found");
-    let mut s = String::new();
-    f.read_to_string(&mut s).expect("Unable to load file");
+pub struct Day9;
 
-    let seqs = 
-        s.lines()
-            .map(|l| 
-                l.split(" ").map(|n| 
-                    n.parse::<i32>().expect("")).collect::<Vec<_>>()
-                ).collect::<Vec<_>>();
+impl Solution for Day9 {
+    type Input = Vec<Vec<i32>>;
+    type Part1 = i32;
+    type Part2 = i32;
 
-    let extrapolated = seqs.iter().map(|s| extrapolate(s)).sum::<i32>();
+    fn day(&self) -> u8 {
+        9
+    }
 
-    println!("Part 1: {}", extrapolated);
+    fn parse(&self, s: &str) -> Result<Vec<Vec<i32>>> {
+        parse_lines(self.day(), s, |l| separated_list1(tag(" "), i32)(l))
+    }
 
-    let extrapolated_beginnings = seqs.iter().map(|s| extrapolate_beginning(s)).sum::<i32>();
+    fn part1(&self, seqs: &Vec<Vec<i32>>) -> Result<i32> {
+        Ok(seqs.iter().map(|s| extrapolate(s)).sum::<i32>())
+    }
 
-    println!("Part 2: {}", extrapolated_beginnings);
+    fn part2(&self, seqs: &Vec<Vec<i32>>) -> Result<i32> {
+        Ok(seqs.iter().map(|s| extrapolate_beginning(s)).sum::<i32>())
+    }
 }
 
-
 #[cfg(test)]
+#[allow(clippy::useless_vec)]
 mod tests {
     use super::*;
 
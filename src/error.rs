@@ -0,0 +1,98 @@
+use std::fmt;
+use std::ops::Range;
+
+/// Everything that can go wrong while solving a day, other than reading its input.
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The input didn't match the puzzle's format. `line` and `column` are 1-based
+    /// and `text` is the whole offending line.
+    Parse { day: u8, line: usize, column: usize, text: String },
+    /// The expected answers file is malformed, or gives an answer outside a `[dayN]` table.
+    Answers { line: usize, column: usize, text: String },
+    /// A Day 1 vocabulary file line isn't `token = digit`.
+    Vocabulary { line: usize, column: usize, text: String },
+    /// A Day 1 vocabulary token was given a value that isn't a single digit.
+    NotADigit { token: String, value: u32 },
+    /// A game shows cubes of a colour the bag doesn't list.
+    UnknownColour { day: u8, game: u32, colour: String },
+    /// Two scratchcards have the same ID.
+    DuplicateCard { day: u8, card: u32 },
+    /// A scratchcard wins a copy of a card that isn't in the table.
+    CardOutOfRange { day: u8, card: u32, won: i64 },
+    /// Scratchcards win copies of each other in a loop, so the copies never stop.
+    CardCycle { day: u8, card: u32 },
+    /// A scratchcard ends up with more copies than fit in a `u64`.
+    TooManyCopies { day: u8, card: u32 },
+    /// A network node was referenced but never defined.
+    MissingNode { day: u8, node: String },
+    /// The ghosts are never all on Z nodes at the same time.
+    NoCommonStep { day: u8 },
+    /// The ghosts only meet on Z nodes after more steps than fit in a `u64`.
+    StepTooLarge { day: u8 },
+    /// No network node ends with A, so there are no ghosts to follow.
+    NoStartNodes { day: u8 },
+    /// Part 2 reads the seeds as start and length pairs, but one is left over.
+    UnpairedSeed { day: u8, seed: u64 },
+    /// Every seed range in part 2 has a length of zero, so there are no seeds.
+    NoSeeds { day: u8 },
+    /// No map converts from `category` on the way to the requested category.
+    MissingMap { day: u8, category: String },
+    /// Two entries of the same map cover some of the same source values.
+    OverlappingRanges { day: u8, map: String, first: Range<u64>, second: Range<u64> },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+fn write_location(f: &mut fmt::Formatter<'_>, column: usize, text: &str) -> fmt::Result {
+    writeln!(f, "    {}", text)?;
+    write!(f, "    {}^", " ".repeat(column - 1))
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse { day, line, column, text } => {
+                writeln!(f, "day {}: unable to parse input at line {}, column {}", day, line, column)?;
+                write_location(f, *column, text)
+            },
+            Error::Answers { line, column, text } => {
+                writeln!(f, "unable to read expected answers at line {}, column {}", line, column)?;
+                write_location(f, *column, text)
+            },
+            Error::Vocabulary { line, column, text } => {
+                writeln!(f, "unable to read vocabulary at line {}, column {}", line, column)?;
+                write_location(f, *column, text)
+            },
+            Error::NotADigit { token, value } =>
+                write!(f, "{} can't stand for {}, which isn't a single digit", token, value),
+            Error::UnknownColour { day, game, colour } =>
+                write!(f, "day {}: game {} shows {} cubes, but the bag has no {}", day, game, colour, colour),
+            Error::DuplicateCard { day, card } =>
+                write!(f, "day {}: there is more than one card {}", day, card),
+            Error::CardOutOfRange { day, card, won } =>
+                write!(f, "day {}: card {} wins a copy of card {}, which doesn't exist", day, card, won),
+            Error::CardCycle { day, card } =>
+                write!(f, "day {}: card {} wins copies of itself through other cards", day, card),
+            Error::TooManyCopies { day, card } =>
+                write!(f, "day {}: card {} has too many copies to count", day, card),
+            Error::MissingNode { day, node } =>
+                write!(f, "day {}: node {} is referenced but never defined", day, node),
+            Error::NoCommonStep { day } =>
+                write!(f, "day {}: the ghosts never all reach Z nodes on the same step", day),
+            Error::StepTooLarge { day } =>
+                write!(f, "day {}: the ghosts meet on Z nodes after more than {} steps", day, u64::MAX),
+            Error::NoStartNodes { day } =>
+                write!(f, "day {}: there are no nodes ending with A to start from", day),
+            Error::UnpairedSeed { day, seed } =>
+                write!(f, "day {}: seeds should come in start and length pairs, but {} has no length", day, seed),
+            Error::NoSeeds { day } =>
+                write!(f, "day {}: every seed range is empty", day),
+            Error::MissingMap { day, category } =>
+                write!(f, "day {}: there is no map from {}", day, category),
+            Error::OverlappingRanges { day, map, first, second } =>
+                write!(f, "day {}: {} map has overlapping entries {:?} and {:?}", day, map, first, second),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,252 @@
+use std::ops::{Index, IndexMut, Range};
+
+use crate::error::Result;
+use crate::parse::error_at;
+
+const OFFSETS_4: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+const OFFSETS_8: [(isize, isize); 8] = [
+    (-1, -1), (0, -1), (1, -1),
+    (-1, 0), (1, 0),
+    (-1, 1), (0, 1), (1, 1)];
+
+/// A rectangular grid of cells stored row by row. Positions are `(x, y)` with
+/// `(0, 0)` in the top left.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Builds a grid from one character per cell, one line per row. Blank lines are
+    /// skipped and every other line must be as long as the first.
+    pub fn parse<F: FnMut(char) -> T>(day: u8, input: &str, mut to_cell: F) -> Result<Grid<T>> {
+        let lines = input.lines().filter(|l| !l.is_empty()).collect::<Vec<_>>();
+        let width = lines.first().map_or(0, |l| l.chars().count());
+        let mut cells = Vec::with_capacity(width * lines.len());
+
+        for line in lines.iter() {
+            let length = line.chars().count();
+
+            if length != width {
+                let column = line.char_indices().nth(width.min(length)).map_or(line.len(), |(i, _)| i);
+                return Err(error_at(day, input, &line[column..]));
+            }
+
+            cells.extend(line.chars().map(&mut to_cell));
+        }
+
+        Ok(Grid { width: width, height: lines.len(), cells: cells })
+    }
+
+    /// A grid of the same shape with `f` applied to every cell.
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid<U> {
+        Grid { width: self.width, height: self.height, cells: self.cells.iter().map(f).collect() }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if x < self.width && y < self.height {
+            self.cells.get(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        if x < self.width && y < self.height {
+            self.cells.get_mut(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    fn offset(&self, x: usize, y: usize, (dx, dy): (isize, isize)) -> Option<(usize, usize)> {
+        let nx = x.checked_add_signed(dx)?;
+        let ny = y.checked_add_signed(dy)?;
+
+        if nx < self.width && ny < self.height {
+            Some((nx, ny))
+        } else {
+            None
+        }
+    }
+
+    /// The orthogonal neighbours of `(x, y)` that are inside the grid.
+    pub fn neighbours4(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        OFFSETS_4.iter().filter_map(move |&d| self.offset(x, y, d))
+    }
+
+    /// The orthogonal and diagonal neighbours of `(x, y)` that are inside the grid.
+    pub fn neighbours8(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        OFFSETS_8.iter().filter_map(move |&d| self.offset(x, y, d))
+    }
+
+    /// Every position in reading order.
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width;
+
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        assert!(y < self.height, "Position is outside the grid");
+
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // chunks panics on a zero width, which only happens for an empty grid
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        assert!(x < self.width, "Position is outside the grid");
+
+        self.cells.iter().skip(x).step_by(self.width.max(1)).take(self.height)
+    }
+
+    /// Horizontal runs of cells matching `pred`, as `(y, xs)`, in reading order.
+    pub fn row_runs<P: Fn(&T) -> bool>(&self, pred: P) -> Vec<(usize, Range<usize>)> {
+        let mut runs = Vec::new();
+
+        for (y, row) in self.rows().enumerate() {
+            let mut x = 0;
+
+            while x < row.len() {
+                if pred(&row[x]) {
+                    let start = x;
+
+                    while x < row.len() && pred(&row[x]) {
+                        x += 1;
+                    }
+
+                    runs.push((y, start..x));
+                } else {
+                    x += 1;
+                }
+            }
+        }
+
+        runs
+    }
+
+    /// Groups of cells matching `pred` that are joined through their orthogonal
+    /// neighbours. Each region's positions are in the order they were found.
+    pub fn regions<P: Fn(&T) -> bool>(&self, pred: P) -> Vec<Vec<(usize, usize)>> {
+        let mut seen = vec!(false; self.cells.len());
+        let mut regions = Vec::new();
+
+        for (x, y) in self.positions() {
+            if seen[y * self.width + x] || !pred(&self[(x, y)]) {
+                continue;
+            }
+
+            seen[y * self.width + x] = true;
+
+            let mut region = vec!((x, y));
+            let mut i = 0;
+
+            while i < region.len() {
+                let (cx, cy) = region[i];
+
+                for (nx, ny) in self.neighbours4(cx, cy) {
+                    if !seen[ny * self.width + nx] && pred(&self[(nx, ny)]) {
+                        seen[ny * self.width + nx] = true;
+                        region.push((nx, ny));
+                    }
+                }
+
+                i += 1;
+            }
+
+            regions.push(region);
+        }
+
+        regions
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        self.get(x, y).expect("Position is outside the grid")
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        self.get_mut(x, y).expect("Position is outside the grid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Error;
+
+    use super::*;
+
+    fn example() -> Grid<char> {
+        Grid::parse(0, "ab.\n.cd\n..e\n", |c| c).expect("Unable to parse grid")
+    }
+
+    #[test]
+    fn test_parse() {
+        let grid = example();
+
+        assert_eq!((grid.width(), grid.height()), (3, 3));
+        assert_eq!(grid[(1, 1)], 'c');
+        assert_eq!(grid.get(3, 0), None);
+        assert_eq!(grid.row(2), &['.', '.', 'e']);
+        assert_eq!(grid.column(2).collect::<String>(), ".de");
+        assert_eq!(grid.rows().count(), 3);
+
+        assert_eq!(
+            Grid::parse(3, "abc\nab\n", |c| c),
+            Err(Error::Parse { day: 3, line: 2, column: 3, text: "ab".to_string() }));
+        assert_eq!(
+            Grid::parse(3, "abc\nabcd\n", |c| c),
+            Err(Error::Parse { day: 3, line: 2, column: 4, text: "abcd".to_string() }));
+    }
+
+    #[test]
+    #[should_panic(expected = "Position is outside the grid")]
+    fn test_column_outside() {
+        example().column(4).count();
+    }
+
+    #[test]
+    #[should_panic(expected = "Position is outside the grid")]
+    fn test_row_outside() {
+        example().row(3);
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = example();
+
+        assert_eq!(grid.neighbours4(0, 0).collect::<Vec<_>>(), vec!((1, 0), (0, 1)));
+        assert_eq!(grid.neighbours8(0, 0).collect::<Vec<_>>(), vec!((1, 0), (0, 1), (1, 1)));
+        assert_eq!(grid.neighbours8(1, 1).count(), 8);
+        assert_eq!(grid.neighbours8(2, 2).collect::<Vec<_>>(), vec!((1, 1), (2, 1), (1, 2)));
+    }
+
+    #[test]
+    fn test_runs_and_regions() {
+        let grid = example();
+        let letter = |c: &char| c.is_alphabetic();
+
+        assert_eq!(grid.row_runs(letter), vec!((0, 0..2), (1, 1..3), (2, 2..3)));
+        assert_eq!(grid.regions(letter), vec!(vec!((0, 0), (1, 0), (1, 1), (2, 1), (2, 2))));
+        assert_eq!(grid.regions(|c| *c == '.').len(), 2);
+    }
+}
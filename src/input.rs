@@ -0,0 +1,98 @@
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_DATA_DIR: &str = "data";
+
+/// Environment variable that overrides the default data directory.
+pub const DATA_DIR_VAR: &str = "ADVENT2023_DATA_DIR";
+
+/// Where a day's puzzle input is read from.
+#[derive(Debug, PartialEq, Clone)]
+pub enum InputSource {
+    /// `day<N>.txt` inside the data directory.
+    DataDir,
+    Path(PathBuf),
+    Stdin,
+}
+
+impl InputSource {
+    /// Interprets an `--input` argument, where `-` means stdin.
+    pub fn from_arg(arg: &str) -> InputSource {
+        if arg == "-" {
+            InputSource::Stdin
+        } else {
+            InputSource::Path(PathBuf::from(arg))
+        }
+    }
+}
+
+/// Loads puzzle inputs, so the day modules only ever see the text.
+#[derive(Debug)]
+pub struct InputLoader {
+    data_dir: PathBuf,
+}
+
+impl InputLoader {
+    pub fn new<P: AsRef<Path>>(data_dir: P) -> InputLoader {
+        InputLoader { data_dir: data_dir.as_ref().to_path_buf() }
+    }
+
+    /// Uses `ADVENT2023_DATA_DIR` when it is set, otherwise `data`.
+    pub fn from_env() -> InputLoader {
+        match env::var_os(DATA_DIR_VAR) {
+            Some(dir) => InputLoader::new(dir),
+            None => InputLoader::new(DEFAULT_DATA_DIR),
+        }
+    }
+
+    /// The expected answers for the inputs in the data directory.
+    pub fn answers_path(&self) -> PathBuf {
+        self.data_dir.join("answers.toml")
+    }
+
+    pub fn path_for(&self, day: u8) -> PathBuf {
+        self.data_dir.join(format!("day{}.txt", day))
+    }
+
+    pub fn load(&self, day: u8, source: &InputSource) -> io::Result<String> {
+        match source {
+            InputSource::DataDir => fs::read_to_string(self.path_for(day)),
+            InputSource::Path(path) => fs::read_to_string(path),
+            InputSource::Stdin => {
+                let mut s = String::new();
+                io::stdin().read_to_string(&mut s)?;
+                Ok(s)
+            },
+        }
+    }
+
+    /// Describes where `source` points for error messages.
+    pub fn describe(&self, day: u8, source: &InputSource) -> String {
+        match source {
+            InputSource::DataDir => self.path_for(day).display().to_string(),
+            InputSource::Path(path) => path.display().to_string(),
+            InputSource::Stdin => "stdin".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let loader = InputLoader::new(root.join("data"));
+        let example = root.join("tests").join("fixtures").join("day7.txt");
+
+        assert_eq!(loader.path_for(7), root.join("data").join("day7.txt"));
+        assert_eq!(
+            loader.load(7, &InputSource::Path(example)).expect("Unable to load file").lines().count(),
+            5);
+        assert!(InputLoader::new(root.join("missing")).load(7, &InputSource::DataDir).is_err());
+        assert_eq!(InputSource::from_arg("-"), InputSource::Stdin);
+    }
+}
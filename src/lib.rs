@@ -0,0 +1,21 @@
+pub mod answers;
+pub mod bench;
+pub mod cli;
+pub mod day1;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+pub mod error;
+pub mod grid;
+pub mod input;
+pub mod matcher;
+pub mod parse;
+pub mod solution;
+
+#[cfg(test)]
+mod testing;
@@ -1,23 +1,209 @@
-mod day1;
-mod day2;
-mod day3;
-mod day4;
-mod day5;
-mod day6;
-mod day7;
-mod day8;
-mod day9;
-
-fn main() {
-    println!("Advent of Code 2023!");
-
-    day1::run_day1();
-    day2::run_day2();
-    day3::run_day3();
-    day4::run_day4();
-    day5::run_day5();
-    day6::run_day6();
-    day7::run_day7();
-    day8::run_day8();
-    day9::run_day9();
+use std::env;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use advent2023::answers::{ExpectedAnswers, Verdict};
+use advent2023::bench;
+use advent2023::cli::{self, Command, Parts};
+use advent2023::input::{InputLoader, InputSource};
+use advent2023::solution::{self, DynSolution};
+
+#[derive(Default)]
+struct Tally {
+    passed: usize,
+    failed: usize,
+    unknown: usize,
+}
+
+fn run_day(solution: &dyn DynSolution, parts: Parts, input: &str, expected: &ExpectedAnswers, tally: &mut Tally) -> bool {
+    let answers = match solution.run(input, parts) {
+        Ok(answers) => answers,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return false;
+        }
+    };
+
+    let mut correct = true;
+
+    for (part, answer) in [(1, answers.part1), (2, answers.part2)] {
+        if let Some(answer) = answer {
+            let verdict = expected.check(solution.day(), part, &answer);
+
+            println!("Part {}: {} ({})", part, answer, verdict);
+
+            match verdict {
+                Verdict::Pass => tally.passed += 1,
+                Verdict::Fail { .. } => {
+                    tally.failed += 1;
+                    correct = false;
+                },
+                Verdict::Unknown => tally.unknown += 1,
+            }
+        }
+    }
+
+    correct
+}
+
+/// Answers from `--answers` when given, otherwise those kept alongside the data
+/// directory's inputs. Other inputs have nothing to check against.
+fn load_answers(path: Option<PathBuf>, source: &InputSource, loader: &InputLoader) -> Result<ExpectedAnswers, String> {
+    let path = match (path, source) {
+        (Some(path), _) if !path.exists() => return Err(format!("{} does not exist", path.display())),
+        (Some(path), _) => path,
+        (None, InputSource::DataDir) => loader.answers_path(),
+        (None, _) => return Ok(ExpectedAnswers::default()),
+    };
+
+    match ExpectedAnswers::load(&path) {
+        Ok(Ok(answers)) => Ok(answers),
+        Ok(Err(e)) => Err(format!("{}: {}", path.display(), e)),
+        Err(e) => Err(format!("unable to read {}: {}", path.display(), e)),
+    }
+}
+
+/// The solutions for `days`, or every registered day when none are given.
+fn select_days(registry: &[Box<dyn DynSolution>], days: Option<Vec<u8>>) -> Result<Vec<&dyn DynSolution>, String> {
+    match days {
+        None => Ok(registry.iter().map(|s| s.as_ref()).collect()),
+        Some(days) => days.iter()
+            .map(|&day| registry.iter()
+                .find(|s| s.day() == day)
+                .map(|s| s.as_ref())
+                .ok_or(format!("Day {} is not available, see `advent2023 list`", day)))
+            .collect(),
+    }
+}
+
+fn run_days(days: Option<Vec<u8>>, parts: Parts, source: InputSource, loader: InputLoader, expected: ExpectedAnswers) -> ExitCode {
+    let registry = solution::registry();
+    let solutions = match select_days(&registry, days) {
+        Ok(solutions) => solutions,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut failed = Vec::new();
+    let mut tally = Tally::default();
+
+    for solution in solutions {
+        let day = solution.day();
+
+        println!("Day {}!", day);
+
+        let input = match loader.load(day, &source) {
+            Ok(input) => input,
+            Err(e) => {
+                eprintln!("Unable to read input for day {} from {}: {}", day, loader.describe(day, &source), e);
+                failed.push(day);
+                continue;
+            }
+        };
+
+        let correct = panic::catch_unwind(AssertUnwindSafe(|| 
+            run_day(solution, parts, &input, &expected, &mut tally)));
+
+        if !correct.unwrap_or(false) {
+            failed.push(day);
+        }
+    }
+
+    println!("Answers: {} passed, {} failed, {} unknown", tally.passed, tally.failed, tally.unknown);
+
+    if failed.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        eprintln!("Failed days: {:?}", failed);
+        ExitCode::FAILURE
+    }
+}
+
+fn bench_days(days: Option<Vec<u8>>, runs: usize, loader: InputLoader, json: bool) -> ExitCode {
+    let registry = solution::registry();
+    let solutions = match select_days(&registry, days) {
+        Ok(solutions) => solutions,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut benches = Vec::new();
+
+    for solution in solutions {
+        let day = solution.day();
+
+        let result = loader.load(day, &InputSource::DataDir)
+            .map_err(|e| format!("Unable to read input for day {} from {}: {}", day, loader.path_for(day).display(), e))
+            .and_then(|input| bench::bench_day(solution, &input, runs).map_err(|e| format!("error: {}", e)));
+
+        match result {
+            Ok(bench) => benches.push(bench),
+            Err(e) => {
+                eprintln!("{}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    if json {
+        println!("{}", bench::format_json(runs, &benches));
+    } else {
+        println!("Timings over {} runs", runs);
+        print!("{}", bench::format_table(&benches));
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn main() -> ExitCode {
+    let command = match cli::parse_args(env::args().skip(1)) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, cli::USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match command {
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            ExitCode::SUCCESS
+        },
+        Command::List => {
+            for solution in solution::registry() {
+                println!("Day {}", solution.day());
+            }
+            ExitCode::SUCCESS
+        },
+        Command::Run { days, parts, input, data_dir, answers } => {
+            let loader = match data_dir {
+                Some(dir) => InputLoader::new(dir),
+                None => InputLoader::from_env(),
+            };
+
+            let expected = match load_answers(answers, &input, &loader) {
+                Ok(expected) => expected,
+                Err(e) => {
+                    eprintln!("error: {}", e);
+                    return ExitCode::FAILURE;
+                }
+            };
+
+            println!("Advent of Code 2023!");
+            run_days(days, parts, input, loader, expected)
+        },
+        Command::Bench { days, runs, data_dir, json } => {
+            let loader = match data_dir {
+                Some(dir) => InputLoader::new(dir),
+                None => InputLoader::from_env(),
+            };
+
+            bench_days(days, runs, loader, json)
+        },
+    }
 }
@@ -0,0 +1,141 @@
+use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
+
+/// Where a token was found, as byte offsets into the text, and its value.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Match<T> {
+    pub span: Range<usize>,
+    pub value: T,
+}
+
+#[derive(Debug)]
+struct Node<T> {
+    next: HashMap<char, usize>,
+    // The longest proper suffix of this node's prefix that is also in the trie
+    fail: usize,
+    // Every token ending here, as its length in bytes, longest first
+    outputs: Vec<(usize, T)>,
+}
+
+impl<T> Node<T> {
+    fn new() -> Node<T> {
+        Node { next: HashMap::new(), fail: 0, outputs: Vec::new() }
+    }
+}
+
+/// An Aho-Corasick automaton that finds every occurrence of a set of tokens in one
+/// pass over the text, including occurrences that overlap.
+#[derive(Debug)]
+pub struct Matcher<T> {
+    nodes: Vec<Node<T>>,
+}
+
+impl<T: Clone> Matcher<T> {
+    /// Builds a matcher for `tokens`. Empty tokens never match, and when a token is
+    /// given twice the last value wins.
+    pub fn new<'a, I: IntoIterator<Item = (&'a str, T)>>(tokens: I) -> Matcher<T> {
+        let mut nodes = vec!(Node::new());
+
+        for (token, value) in tokens {
+            if token.is_empty() {
+                continue;
+            }
+
+            let mut state = 0;
+
+            for c in token.chars() {
+                state = match nodes[state].next.get(&c) {
+                    Some(&next) => next,
+                    None => {
+                        nodes.push(Node::new());
+                        let next = nodes.len() - 1;
+                        nodes[state].next.insert(c, next);
+                        next
+                    },
+                };
+            }
+
+            nodes[state].outputs = vec!((token.len(), value));
+        }
+
+        // Breadth first, so every node's failure target is finished before its children
+        let mut queue = nodes[0].next.values().copied().collect::<VecDeque<_>>();
+
+        while let Some(state) = queue.pop_front() {
+            let children = nodes[state].next.iter().map(|(&c, &child)| (c, child)).collect::<Vec<_>>();
+
+            for (c, child) in children {
+                let mut fail = nodes[state].fail;
+
+                while fail != 0 && !nodes[fail].next.contains_key(&c) {
+                    fail = nodes[fail].fail;
+                }
+
+                let fail = nodes[fail].next.get(&c).copied().filter(|&f| f != child).unwrap_or(0);
+                let inherited = nodes[fail].outputs.clone();
+
+                nodes[child].fail = fail;
+                nodes[child].outputs.extend(inherited);
+                queue.push_back(child);
+            }
+        }
+
+        Matcher { nodes: nodes }
+    }
+
+    fn step(&self, mut state: usize, c: char) -> usize {
+        loop {
+            if let Some(&next) = self.nodes[state].next.get(&c) {
+                return next;
+            }
+
+            if state == 0 {
+                return 0;
+            }
+
+            state = self.nodes[state].fail;
+        }
+    }
+
+    /// Every occurrence of a token in `text`, ordered by where they end and then
+    /// longest first.
+    pub fn find_all<'a>(&'a self, text: &'a str) -> impl Iterator<Item = Match<T>> + 'a {
+        let mut state = 0;
+
+        text.char_indices().flat_map(move |(i, c)| {
+            state = self.step(state, c);
+
+            let end = i + c.len_utf8();
+
+            self.nodes[state].outputs.iter()
+                .map(move |(length, value)| Match { span: end - length..end, value: value.clone() })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(matcher: &Matcher<u32>, text: &str) -> Vec<(Range<usize>, u32)> {
+        matcher.find_all(text).map(|m| (m.span, m.value)).collect()
+    }
+
+    #[test]
+    fn test_overlapping_matches() {
+        let matcher = Matcher::new([("he", 1), ("she", 2), ("his", 3), ("hers", 4)]);
+
+        assert_eq!(spans(&matcher, "ushers"), vec!((1..4, 2), (2..4, 1), (2..6, 4)));
+        assert_eq!(spans(&matcher, "ahishers"), vec!((1..4, 3), (3..6, 2), (4..6, 1), (4..8, 4)));
+        assert_eq!(spans(&matcher, "xyz"), vec!());
+    }
+
+    #[test]
+    fn test_repeated_and_unicode_tokens() {
+        let matcher = Matcher::new([("aa", 1), ("", 2), ("drei", 3), ("drei", 4), ("zwölf", 5)]);
+
+        assert_eq!(spans(&matcher, "aaa"), vec!((0..2, 1), (1..3, 1)));
+        assert_eq!(spans(&matcher, "zweidrei"), vec!((4..8, 4)));
+        assert_eq!(spans(&matcher, "zwölfzwölf"), vec!((0..6, 5), (6..12, 5)));
+    }
+}
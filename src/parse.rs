@@ -0,0 +1,73 @@
+use nom::IResult;
+
+use crate::error::{Error, Result};
+
+/// Builds a parse error pointing at the start of `remaining`, which must be a slice of `input`.
+pub fn error_at(day: u8, input: &str, remaining: &str) -> Error {
+    let offset = remaining.as_ptr() as usize - input.as_ptr() as usize;
+    let line_start = input[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = input[offset..].find('\n').map_or(input.len(), |i| offset + i);
+
+    Error::Parse {
+        day: day,
+        line: input[..offset].matches('\n').count() + 1,
+        column: input[line_start..offset].chars().count() + 1,
+        text: input[line_start..line_end].trim_end_matches('\r').to_string(),
+    }
+}
+
+fn parse_within<'a, T, P>(day: u8, input: &'a str, text: &'a str, parser: &mut P) -> Result<T>
+where
+    P: FnMut(&'a str) -> IResult<&'a str, T>,
+{
+    match parser(text) {
+        Ok((rest, value)) if rest.trim().is_empty() => Ok(value),
+        Ok((rest, _)) => Err(error_at(day, input, rest)),
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => Err(error_at(day, input, e.input)),
+        Err(nom::Err::Incomplete(_)) => Err(error_at(day, input, &text[text.len()..])),
+    }
+}
+
+/// Runs `parser` over the whole input, which must all be consumed apart from trailing whitespace.
+pub fn parse_all<'a, T, P>(day: u8, input: &'a str, mut parser: P) -> Result<T>
+where
+    P: FnMut(&'a str) -> IResult<&'a str, T>,
+{
+    parse_within(day, input, input, &mut parser)
+}
+
+/// Runs `parser` over every non-empty line of the input, each of which must be fully consumed.
+pub fn parse_lines<'a, T, P>(day: u8, input: &'a str, mut parser: P) -> Result<Vec<T>>
+where
+    P: FnMut(&'a str) -> IResult<&'a str, T>,
+{
+    input.lines()
+        .filter(|l| !l.is_empty())
+        .map(|l| parse_within(day, input, l, &mut parser))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::character::complete::u32;
+    use nom::multi::separated_list1;
+    use nom::bytes::complete::tag;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_lines_error_position() {
+        let input = "1 2 3\n4 5 6\n7 x 9\n";
+        let result = parse_lines(9, input, |l| separated_list1(tag(" "), u32)(l));
+
+        assert_eq!(result, Err(Error::Parse { day: 9, line: 3, column: 2, text: "7 x 9".to_string() }));
+    }
+
+    #[test]
+    fn test_parse_all() {
+        assert_eq!(parse_all(1, "12\n\n", u32), Ok(12));
+        assert_eq!(
+            parse_all(1, "12\n34", u32),
+            Err(Error::Parse { day: 1, line: 1, column: 3, text: "12".to_string() }));
+    }
+}
@@ -0,0 +1,99 @@
+use std::fmt::Display;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use crate::cli::Parts;
+use crate::error::Result;
+use crate::{day1, day2, day3, day4, day5, day6, day7, day8, day9};
+
+/// A day's puzzle: the input is parsed once and then both parts are solved from it.
+pub trait Solution {
+    type Input;
+    type Part1: Display;
+    type Part2: Display;
+
+    fn day(&self) -> u8;
+    fn parse(&self, input: &str) -> Result<Self::Input>;
+    fn part1(&self, input: &Self::Input) -> Result<Self::Part1>;
+    fn part2(&self, input: &Self::Input) -> Result<Self::Part2>;
+}
+
+/// The formatted answers from running a day, `None` for parts that weren't requested.
+#[derive(Debug, PartialEq)]
+pub struct Answers {
+    pub part1: Option<String>,
+    pub part2: Option<String>,
+}
+
+/// How long one run of a day spent in each phase.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Timings {
+    pub parse: Duration,
+    pub part1: Duration,
+    pub part2: Duration,
+}
+
+/// Object-safe view of a `Solution`, so days with different input and answer types
+/// can live in the same registry.
+pub trait DynSolution {
+    fn day(&self) -> u8;
+    fn run(&self, input: &str, parts: Parts) -> Result<Answers>;
+    /// Parses `input` and solves both parts, timing each step separately.
+    fn time(&self, input: &str) -> Result<Timings>;
+}
+
+impl<S: Solution> DynSolution for S {
+    fn day(&self) -> u8 {
+        Solution::day(self)
+    }
+
+    fn run(&self, input: &str, parts: Parts) -> Result<Answers> {
+        let input = self.parse(input)?;
+
+        Ok(Answers {
+            part1: if parts.one { Some(self.part1(&input)?.to_string()) } else { None },
+            part2: if parts.two { Some(self.part2(&input)?.to_string()) } else { None },
+        })
+    }
+
+    fn time(&self, input: &str) -> Result<Timings> {
+        // black_box stops the answers being optimised away, but they aren't formatted
+        // so the timings only cover the solving
+        let start = Instant::now();
+        let input = black_box(self.parse(input)?);
+        let parsed = Instant::now();
+        black_box(self.part1(&input)?);
+        let solved1 = Instant::now();
+        black_box(self.part2(&input)?);
+        let solved2 = Instant::now();
+
+        Ok(Timings { parse: parsed - start, part1: solved1 - parsed, part2: solved2 - solved1 })
+    }
+}
+
+/// Every available day, in order.
+pub fn registry() -> Vec<Box<dyn DynSolution>> {
+    vec!(
+        Box::new(day1::Day1),
+        Box::new(day2::Day2),
+        Box::new(day3::Day3),
+        Box::new(day4::Day4),
+        Box::new(day5::Day5),
+        Box::new(day6::Day6),
+        Box::new(day7::Day7),
+        Box::new(day8::Day8),
+        Box::new(day9::Day9),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_order() {
+        let days = registry().iter().map(|s| s.day()).collect::<Vec<_>>();
+
+        assert_eq!(days, (1..=9).collect::<Vec<_>>());
+    }
+}
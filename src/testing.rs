@@ -0,0 +1,19 @@
+//! Helpers shared by the unit tests.
+
+/// A xorshift generator, so randomised tests see the same values on every run.
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    pub fn new() -> XorShift {
+        XorShift { state: 0x2545f4914f6cdd1d }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+}
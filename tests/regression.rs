@@ -0,0 +1,97 @@
+//! Runs every registered day against the puzzle examples in `tests/fixtures`, and
+//! against the real inputs in `data` for the days that have one.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use advent2023::answers::{ExpectedAnswers, Verdict};
+use advent2023::cli::Parts;
+use advent2023::input::{InputLoader, InputSource};
+use advent2023::solution::registry;
+
+fn root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn load_answers(path: &Path) -> ExpectedAnswers {
+    ExpectedAnswers::load(path)
+        .expect("Unable to read answers")
+        .unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+fn only(part: u8) -> Parts {
+    Parts { one: part == 1, two: part == 2 }
+}
+
+/// Runs one part of `day` on `input`, describing the problem if the answer isn't
+/// the expected one.
+fn check_part(day: u8, part: u8, input: &str, expected: &ExpectedAnswers) -> Option<String> {
+    let solution = registry().into_iter().find(|s| s.day() == day).expect("Day is registered");
+
+    let answers = match solution.run(input, only(part)) {
+        Ok(answers) => answers,
+        Err(e) => return Some(format!("day {} part {}: {}", day, part, e)),
+    };
+
+    let answer = if part == 1 { answers.part1 } else { answers.part2 };
+    let answer = answer.expect("Requested part has an answer");
+
+    match expected.check(day, part, &answer) {
+        Verdict::Pass => None,
+        verdict => Some(format!("day {} part {}: {} ({})", day, part, answer, verdict)),
+    }
+}
+
+#[test]
+fn test_examples() {
+    let fixtures = root().join("tests").join("fixtures");
+    let expected = load_answers(&fixtures.join("answers.toml"));
+    let mut failures = Vec::new();
+
+    for day in registry().iter().map(|s| s.day()) {
+        for part in [1, 2] {
+            // A part with its own example uses that, otherwise the day's example
+            let part_path = fixtures.join(format!("day{}_part{}.txt", day, part));
+            let path = if part_path.exists() { part_path } else { fixtures.join(format!("day{}.txt", day)) };
+
+            let input = fs::read_to_string(&path)
+                .unwrap_or_else(|e| panic!("Unable to read {}: {}", path.display(), e));
+
+            if expected.expected(day, part).is_none() {
+                failures.push(format!("day {} part {}: no expected answer for the example", day, part));
+            } else if let Some(failure) = check_part(day, part, &input, &expected) {
+                failures.push(failure);
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn test_real_inputs() {
+    let loader = InputLoader::new(root().join("data"));
+    let expected = load_answers(&loader.answers_path());
+    let mut failures = Vec::new();
+
+    for day in registry().iter().map(|s| s.day()) {
+        // Puzzle inputs aren't redistributable, so checkouts without them just skip
+        let input = match loader.load(day, &InputSource::DataDir) {
+            Ok(input) => input,
+            Err(_) => {
+                eprintln!("Skipping day {}, {} is missing", day, loader.path_for(day).display());
+                continue;
+            }
+        };
+
+        for part in [1, 2] {
+            if expected.expected(day, part).is_none() {
+                eprintln!("Skipping day {} part {}, no expected answer", day, part);
+            } else if let Some(failure) = check_part(day, part, &input, &expected) {
+                failures.push(failure);
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}